@@ -2,7 +2,7 @@ use crate::prelude::*;
 
 /// A resource that provides a limited view around a certain point
 /// If bounds are exceeded, they are constrained
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Camera {
     pub left_x: i32,
     pub right_x: i32,
@@ -10,6 +10,12 @@ pub struct Camera {
     pub bottom_y: i32,
 }
 
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Camera {
     pub fn new() -> Self {
         Self {
@@ -2,7 +2,7 @@ use crate::prelude::*;
 
 use std::collections::HashSet;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct FieldOfView {
     pub visible_tiles: HashSet<Point>,
     pub radius: i32,
@@ -32,20 +32,95 @@ impl FieldOfView {
 // all components will be in this file for now
 
 /// Denotes a player component, not necessary to contain any fields, will act as a tag
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Player;
 
 /// Denotes an enemy tagged component
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Enemy;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MovesRandomly;
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ChasingPlayer;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// Nothing else can move onto the tile this entity stands on
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BlocksTile;
+
+/// What a monster chasing the player is up to, the monster AI systems move it
+/// from one state to the next based on what it sees and how hurt it is
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AiState {
+    /// stays put until the player comes into view
+    Idle,
+    /// roams around at random
+    Wandering,
+    /// goes straight for its target, remembering where it was last seen
+    Hunting { target: Entity, last_seen: Point },
+    /// the player slipped out of view, look where they were last seen for a while
+    Searching { target: Point, turns: i32 },
+    /// badly hurt, keeps as far away from the player as possible
+    Fleeing,
+}
+
+/// How one creature feels about another
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Reaction {
+    Hostile,
+    Neutral,
+    Friendly,
+}
+
+/// Who a creature sides with, the reaction table decides how the factions treat each other
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Faction {
+    Player,
+    Goblins,
+    Orcs,
+    Ogres,
+    /// wild animals, they go for the player and leave the tribes alone
+    Beasts,
+}
+
+impl Faction {
+    /// The reaction table, how members of this faction treat members of `other`
+    pub fn reaction_to(self, other: Faction) -> Reaction {
+        match (self, other) {
+            (a, b) if a == b => Reaction::Friendly,
+            // ogres keep to themselves unless provoked
+            (Faction::Ogres, _) | (_, Faction::Ogres) => Reaction::Neutral,
+            (Faction::Beasts, Faction::Player) | (Faction::Player, Faction::Beasts) => {
+                Reaction::Hostile
+            }
+            (Faction::Beasts, _) | (_, Faction::Beasts) => Reaction::Neutral,
+            // goblins and orcs hate each other as much as they hate the player
+            _ => Reaction::Hostile,
+        }
+    }
+}
+
+/// This creature was attacked by `by` and is hostile towards it, whatever its faction says
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Provoked {
+    pub by: Entity,
+}
+
+/// Fights on the side of `by` for a number of its actions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Charmed {
+    pub by: Entity,
+    pub turns: i32,
+}
+
+/// Charms the closest monster the user can see for a number of its actions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesCharm {
+    pub turns: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Render {
     pub color: ColorPair,
     pub glyph: FontCharType,
@@ -54,36 +129,368 @@ pub struct Render {
 /// A message indicating that an entity wants to move somewhere
 /// Components can be messages too, and we only have one system processing these to keep duplication
 /// to a minimum
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WantsToMove {
     pub entity: Entity,
     pub destination: Point,
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Health {
     pub current: i32,
     pub max: i32,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Name(pub String);
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// How well an entity fights, `to_hit` is added to the hit roll and `damage` rolled on a hit
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Attack {
+    pub to_hit: i32,
+    pub damage: DiceType,
+}
+
+impl Attack {
+    /// damage is given as a dice string, e.g. "1d6+1"
+    pub fn new(to_hit: i32, damage: &str) -> Self {
+        Self {
+            to_hit,
+            damage: parse_dice_string(damage).expect("Invalid damage dice"),
+        }
+    }
+}
+
+impl Default for Attack {
+    /// anything without an attack of its own flails around
+    fn default() -> Self {
+        Self::new(0, "1d2")
+    }
+}
+
+/// Armour makes an entity harder to hit
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Defense {
+    pub armour: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WantsToAttack {
     pub attacker: Entity,
     pub victim: Entity,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ItemReceived {
     pub receiver: Entity,
     pub item: Entity,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Item;
 
+/// An item in someone's inventory, it no longer has a position of its own
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Carried(pub Entity);
+
+/// A message to use a carried item
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActivateItem {
+    pub used_by: Entity,
+    pub item: Entity,
+    /// the tile picked for items that are thrown or aimed
+    pub target: Option<Point>,
+}
+
+/// A message to put a carried item back on the floor
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WantsToDrop {
+    pub dropped_by: Entity,
+    pub item: Entity,
+}
+
+/// Items that are used up once activated
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Consumable;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesHealing {
+    pub amount: i32,
+}
+
+/// Reveals the whole level to the user
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesDungeonMap;
+
+/// Moves the user to a random spot on the level
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesTeleport;
+
+/// Moves the user to the tile it is aimed at
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesBlink;
+
+/// Widens the field of view of the user for a number of turns
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesVision {
+    pub bonus: i32,
+    pub turns: i32,
+}
+
+/// The field of view radius of this entity is raised by `bonus` until `turns` run out
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VisionBoost {
+    pub bonus: i32,
+    pub turns: i32,
+}
+
+/// Energy an entity spends on every action, nobody acts before they have saved up this much
+pub const ACTION_COST: i32 = 100;
+/// The speed of the player, anything faster acts more often than they do
+pub const NORMAL_SPEED: i32 = 100;
+
+/// Entities gain `speed` energy every tick of the clock and act whenever they have
+/// at least `ACTION_COST` saved up, so fast monsters get several moves per player action
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Energy {
+    pub current: i32,
+    pub speed: i32,
+}
+
+impl Energy {
+    /// Starts out empty, so the newcomer waits for the next tick
+    pub fn new(speed: i32) -> Self {
+        Self { current: 0, speed }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.current >= ACTION_COST
+    }
+}
+
+/// Doubles the speed of this entity for a number of its actions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hasted {
+    pub turns: i32,
+}
+
+/// Halves the speed of this entity for a number of its actions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Slowed {
+    pub turns: i32,
+}
+
+/// Hastes the user for a number of their actions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesHaste {
+    pub turns: i32,
+}
+
+/// Slows every enemy the user can see for a number of their actions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProvidesSlow {
+    pub turns: i32,
+}
+
+/// Loses `damage` health with each of its actions for a number of them
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Poisoned {
+    pub turns: i32,
+    pub damage: i32,
+}
+
+/// Stumbles in a random direction whenever it tries to move, for a number of its actions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Confused {
+    pub turns: i32,
+}
+
+/// Does nothing for a number of its actions, or until something hits it
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Asleep {
+    pub turns: i32,
+}
+
+/// Runs from the player whenever it sees them, for a number of its actions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Afraid {
+    pub turns: i32,
+}
+
+/// One of the timed effects, the status effects system counts them all down
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusEffect {
+    Poison { turns: i32, damage: i32 },
+    Confusion(i32),
+    Sleep(i32),
+    Haste(i32),
+    Slow(i32),
+    Fear(i32),
+}
+
+/// Puts a status effect on its victims, with `chance` percent on every hit by a monster,
+/// on everything in view when used as an item and on whoever steps on a trap
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InflictsStatus {
+    pub effect: StatusEffect,
+    #[serde(default = "always")]
+    pub chance: i32,
+}
+
+fn always() -> i32 {
+    100
+}
+
+/// Needs a target within `range` tiles, for items that are thrown, weapons that shoot
+/// and monsters that attack from afar
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ranged {
+    pub range: i32,
+}
+
+/// An aimed item hits everything within `radius` tiles of its target
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AreaOfEffect {
+    pub radius: i32,
+}
+
+/// An aimed item hurts everything it hits
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InflictsDamage {
+    pub damage: i32,
+}
+
+/// Springs on the first one to step on it, then it is gone
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EquipmentSlot {
+    Weapon,
+    Armour,
+    Shield,
+    Amulet,
+}
+
+impl EquipmentSlot {
+    pub const ALL: [EquipmentSlot; 4] = [
+        EquipmentSlot::Weapon,
+        EquipmentSlot::Armour,
+        EquipmentSlot::Shield,
+        EquipmentSlot::Amulet,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EquipmentSlot::Weapon => "weapon",
+            EquipmentSlot::Armour => "armour",
+            EquipmentSlot::Shield => "shield",
+            EquipmentSlot::Amulet => "amulet",
+        }
+    }
+}
+
+/// An item that can be worn or wielded in the given slot
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Equippable {
+    pub slot: EquipmentSlot,
+}
+
+/// An item that is currently worn or wielded, it stays carried by its owner as well
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Equipped {
+    pub owner: Entity,
+    pub slot: EquipmentSlot,
+}
+
+/// Added to the attack of whoever has this item equipped
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AttackBonus {
+    pub to_hit: i32,
+    pub damage: i32,
+}
+
+/// Added to the defense of whoever has this item equipped
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DefenseBonus {
+    pub armour: i32,
+}
+
+/// Experience needed per level to reach the next one, level 1 needs 20, level 2 needs 40...
+pub const XP_PER_LEVEL: i32 = 20;
+
+/// The character level of an entity and the experience gathered towards the next one
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Experience {
+    pub fn new() -> Self {
+        Self { level: 1, xp: 0 }
+    }
+
+    pub fn next_level(&self) -> i32 {
+        self.level * XP_PER_LEVEL
+    }
+}
+
+/// The experience whoever kills this entity is awarded
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExperienceValue {
+    pub xp: i32,
+}
+
 // yet another lost amulet
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AmuletOfYala;
+
+/// What abilities are cast with, regained one point with each of the caster's actions
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mana {
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Mana {
+    pub fn new(max: i32) -> Self {
+        Self { current: max, max }
+    }
+}
+
+/// An ability known by `owner`, its effects are the same components items use. It can be
+/// cast again once `ready_in` of the owner's actions have passed
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ability {
+    pub owner: Entity,
+    pub mana: i32,
+    pub cooldown: i32,
+    pub ready_in: i32,
+}
+
+/// A message to cast one of the caster's abilities
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WantsToCast {
+    pub caster: Entity,
+    pub ability: Entity,
+    /// the tile picked for abilities that are aimed
+    pub target: Option<Point>,
+}
+
+/// Creatures without one walk, and can't enter deep water
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Locomotion {
+    /// crosses water and lava alike, and is never burnt
+    Flying,
+    /// crosses deep water
+    Swimming,
+}
@@ -0,0 +1,29 @@
+use crate::prelude::*;
+
+/// The depth on which the Amulet of Yala waits, every floor above it has stairs leading down
+pub const FINAL_DEPTH: i32 = 3;
+
+/// A resource tracking how far down the dungeon the player has descended, starting at 1
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Depth(pub i32);
+
+impl Default for Depth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Depth {
+    pub fn new() -> Self {
+        Self(1)
+    }
+
+    pub fn next(&self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    /// the amulet is only placed on the final depth, all others get an exit
+    pub fn is_final(&self) -> bool {
+        self.0 >= FINAL_DEPTH
+    }
+}
@@ -0,0 +1,178 @@
+use crate::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Paths are not followed further than this many steps
+const MAX_DEPTH: f32 = 1024.0;
+/// Fleeing monsters weigh the distance to the player a little heavier than the way
+/// around them, so they would rather run past into open space than into a corner
+const FLEE_FACTOR: f32 = -1.2;
+
+/// A Dijkstra map remembering what it was built for, so it is only rebuilt when the
+/// targets move or the map changes
+struct CachedField {
+    targets: Vec<usize>,
+    revision: u32,
+    dijkstra: DijkstraMap,
+}
+
+impl CachedField {
+    fn is_current(&self, targets: &[usize], map: &Map) -> bool {
+        self.revision == map.revision && self.targets == targets
+    }
+}
+
+/// A resource holding the flow fields all monsters share, instead of every monster
+/// searching the whole map for itself every turn. Walk downhill with
+/// `DijkstraMap::find_lowest_exit` to follow one
+#[derive(Default)]
+pub struct FlowFields {
+    chase: Option<CachedField>,
+    flee: Option<CachedField>,
+    items: Option<CachedField>,
+}
+
+impl FlowFields {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuilds whatever went stale since the last call, except for the flee field
+    /// which waits for `refresh_flee`
+    pub fn refresh(&mut self, map: &Map, player: Point, items: &[Point]) {
+        let player = vec![map_idx(player.x, player.y)];
+        if !self
+            .chase
+            .as_ref()
+            .is_some_and(|f| f.is_current(&player, map))
+        {
+            self.chase = Some(build_field(map, player));
+        }
+
+        let mut items: Vec<usize> = items.iter().map(|pos| map_idx(pos.x, pos.y)).collect();
+        items.sort_unstable();
+        if !self
+            .items
+            .as_ref()
+            .is_some_and(|f| f.is_current(&items, map))
+        {
+            self.items = Some(build_field(map, items));
+        }
+    }
+
+    /// Derives the flee field from the chase field when it went stale. Only worth the
+    /// work while some monster is actually fleeing, so it is left to them to ask for it
+    pub fn refresh_flee(&mut self, map: &Map) {
+        let chase = match &self.chase {
+            Some(chase) => chase,
+            None => return,
+        };
+        if self
+            .flee
+            .as_ref()
+            .is_some_and(|f| f.is_current(&chase.targets, map))
+        {
+            return;
+        }
+
+        self.flee = Some(CachedField {
+            dijkstra: flee_field(map, &chase.dijkstra),
+            targets: chase.targets.clone(),
+            revision: map.revision,
+        });
+    }
+
+    /// Leads towards the player
+    pub fn chase(&self) -> Option<&DijkstraMap> {
+        self.chase.as_ref().map(|field| &field.dijkstra)
+    }
+
+    /// Leads away from the player, as of the last `refresh_flee`
+    pub fn flee(&self) -> Option<&DijkstraMap> {
+        self.flee.as_ref().map(|field| &field.dijkstra)
+    }
+
+    /// Leads towards the closest item lying on the floor
+    pub fn items(&self) -> Option<&DijkstraMap> {
+        self.items.as_ref().map(|field| &field.dijkstra)
+    }
+}
+
+fn build_field(map: &Map, targets: Vec<usize>) -> CachedField {
+    CachedField {
+        dijkstra: DijkstraMap::new(SCREEN_WIDTH, SCREEN_HEIGHT, &targets, map, MAX_DEPTH),
+        targets,
+        revision: map.revision,
+    }
+}
+
+/// A tile waiting to be settled, the heap pops the lowest value first
+struct Frontier {
+    value: f32,
+    idx: usize,
+}
+
+impl PartialEq for Frontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Frontier {}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.value.total_cmp(&self.value)
+    }
+}
+
+/// Turns the distances to the player upside down and lets them settle again, so the
+/// lowest exits lead away from the player but around them when cornered. Every tile
+/// starts out at its inverted distance and is settled once, lowest first
+fn flee_field(map: &Map, chase: &DijkstraMap) -> DijkstraMap {
+    let mut flee = DijkstraMap::new_empty(SCREEN_WIDTH, SCREEN_HEIGHT, MAX_DEPTH);
+    let mut frontier = BinaryHeap::new();
+    flee.map
+        .iter_mut()
+        .zip(chase.map.iter())
+        .enumerate()
+        .filter(|(_, (_, distance))| **distance < f32::MAX)
+        .for_each(|(idx, (flee, distance))| {
+            *flee = distance * FLEE_FACTOR;
+            frontier.push(Frontier { value: *flee, idx });
+        });
+
+    let mut settled = vec![false; flee.map.len()];
+    while let Some(Frontier { value, idx }) = frontier.pop() {
+        if settled[idx] || value > flee.map[idx] {
+            continue;
+        }
+        settled[idx] = true;
+
+        // paths are planned the same both ways, so the cost into a tile works back out too
+        for (exit, _) in map.get_available_exits(idx).iter() {
+            let cost = map
+                .get_available_exits(*exit)
+                .iter()
+                .find(|(back, _)| *back == idx)
+                .map(|(_, cost)| *cost);
+            if let Some(cost) = cost {
+                if value + cost < flee.map[*exit] {
+                    flee.map[*exit] = value + cost;
+                    frontier.push(Frontier {
+                        value: value + cost,
+                        idx: *exit,
+                    });
+                }
+            }
+        }
+    }
+
+    flee
+}
@@ -0,0 +1,102 @@
+use crate::prelude::*;
+
+/// How many entries are kept around for the history view
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CombatOutcome {
+    Miss,
+    Hit,
+    Critical,
+}
+
+/// The result of a single attack, names are copied since the victim might not survive it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CombatEvent {
+    pub attacker: String,
+    pub victim: String,
+    pub outcome: CombatOutcome,
+    pub damage: i32,
+    pub killed: bool,
+}
+
+impl CombatEvent {
+    pub fn describe(&self) -> String {
+        match self.outcome {
+            CombatOutcome::Miss => format!("{} misses {}", self.attacker, self.victim),
+            CombatOutcome::Hit | CombatOutcome::Critical => format!(
+                "{} {} {} for {} damage{}",
+                self.attacker,
+                if self.outcome == CombatOutcome::Critical {
+                    "critically hits"
+                } else {
+                    "hits"
+                },
+                self.victim,
+                self.damage,
+                if self.killed { ", killing them" } else { "" }
+            ),
+        }
+    }
+
+    fn color(&self) -> RGB {
+        RGB::named(match self.outcome {
+            CombatOutcome::Miss => GRAY,
+            CombatOutcome::Hit if !self.killed => ORANGE,
+            _ => RED,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub turn: u32,
+    pub text: String,
+    pub color: RGB,
+}
+
+/// A resource every system can push messages for the player to, the newest entry last
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GameLog {
+    pub entries: Vec<LogEntry>,
+    /// incremented every time the player ends a turn
+    pub turn: u32,
+    /// how many entries the history view is scrolled back
+    #[serde(skip)]
+    pub scroll: usize,
+}
+
+impl GameLog {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            turn: 1,
+            scroll: 0,
+        }
+    }
+
+    pub fn push<S: ToString>(&mut self, text: S, color: (u8, u8, u8)) {
+        self.push_entry(text.to_string(), RGB::named(color));
+    }
+
+    pub fn push_combat(&mut self, event: &CombatEvent) {
+        self.push_entry(event.describe(), event.color());
+    }
+
+    fn push_entry(&mut self, text: String, color: RGB) {
+        self.entries.push(LogEntry {
+            turn: self.turn,
+            text,
+            color,
+        });
+
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    /// The last `count` entries, oldest first
+    pub fn recent(&self, count: usize) -> &[LogEntry] {
+        &self.entries[self.entries.len().saturating_sub(count)..]
+    }
+}
@@ -1,14 +1,22 @@
 // use our own prelude to make it available in main
 use crate::prelude::*;
+use std::path::Path;
 
 // this links the map module to the main project
 mod camera;
 mod components;
+mod depth;
+mod flow_field;
+mod game_log;
 mod map;
 mod map_builder;
+mod map_dump;
 mod options;
+mod save_game;
+mod spatial_index;
 mod spawner;
 mod systems;
+mod targeting;
 mod turn_state;
 
 // this module is convenient for library users and includes most necessary things
@@ -19,15 +27,24 @@ pub mod prelude {
     pub use legion::systems::CommandBuffer;
     pub use legion::world::SubWorld;
     pub use legion::*;
+    // re-export serde derives, most of our types end up in a save game
+    pub use serde::{Deserialize, Serialize};
 
     pub use crate::camera::*;
     pub use crate::components::*;
+    pub use crate::depth::*;
+    pub use crate::flow_field::*;
+    pub use crate::game_log::*;
     // re-export map/player as a public modules
     pub use crate::map::*;
     pub use crate::map_builder::*;
+    pub use crate::map_dump::*;
     pub use crate::options::*;
+    pub use crate::save_game::*;
+    pub use crate::spatial_index::*;
     pub use crate::spawner::*;
     pub use crate::systems::*;
+    pub use crate::targeting::*;
     pub use crate::turn_state::*;
 
     pub const SCREEN_WIDTH: i32 = 80;
@@ -43,21 +60,32 @@ struct State {
     ecs: World,
     resources: Resources,
     input_systems: Schedule,
+    inventory_systems: Schedule,
+    history_systems: Schedule,
+    targeting_systems: Schedule,
     player_systems: Schedule,
     monster_systems: Schedule,
     options: GameOptions,
+    templates: Templates,
+    // feedback from the last save or load, shown in the menu
+    status: Option<String>,
 }
 
 impl State {
     /// creates a new empty state for our game
-    fn new() -> Self {
+    fn new(options: GameOptions, templates: Templates) -> Self {
         Self {
             ecs: World::default(),
             resources: Resources::default(),
             input_systems: build_input_scheduler(),
+            inventory_systems: build_inventory_scheduler(),
+            history_systems: build_history_scheduler(),
+            targeting_systems: build_targeting_scheduler(),
             player_systems: build_player_scheduler(),
             monster_systems: build_monster_scheduler(),
-            options: GameOptions::new(),
+            options,
+            templates,
+            status: None,
         }
     }
 
@@ -65,39 +93,173 @@ impl State {
         self.ecs.clear();
         self.ecs = World::default();
 
-        let mut rng = RandomNumberGenerator::new();
-        let mut camera = Camera::new();
-        let map_builder = MapBuilder::build(&mut rng, &self.options);
-
-        // since we only have one player, we can add them here
+        // since we only have one player, we can add them here, the level will place them
         spawn_player(
             &mut self.ecs,
-            &mut camera,
-            map_builder.player_start,
+            &mut Camera::new(),
+            Point::zero(),
             self.options.player_fov,
         );
-        spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
+        let player = *<Entity>::query()
+            .filter(component::<Player>())
+            .iter(&self.ecs)
+            .next()
+            .expect("Could not find player");
+        for ability in self.templates.abilities.iter().filter(|a| a.starting) {
+            spawner::learn_ability(&mut self.ecs, player, ability);
+        }
 
-        map_builder.monster_spawns.into_iter().for_each(|pos| {
-            spawner::spawn_monster(&mut self.ecs, &mut rng, pos, self.options.monster_fov)
-        });
+        self.resources.insert(Depth::new());
+        self.resources.insert(GameLog::new());
+        self.build_level();
 
         self.options.mode = GameMode::Play;
+    }
+
+    /// Descend one floor, the player (with everything they carry and know) survives the trip,
+    /// all other entities belong to the previous floor and are removed
+    fn advance_level(&mut self) {
+        let player_entity = *<Entity>::query()
+            .filter(component::<Player>())
+            .iter(&self.ecs)
+            .next()
+            .expect("Could not find player");
+
+        let mut keep = vec![player_entity];
+        <(Entity, &Carried)>::query()
+            .iter(&self.ecs)
+            .filter(|(_, carried)| carried.0 == player_entity)
+            .for_each(|(entity, _)| keep.push(*entity));
+        <(Entity, &Ability)>::query()
+            .iter(&self.ecs)
+            .filter(|(_, ability)| ability.owner == player_entity)
+            .for_each(|(entity, _)| keep.push(*entity));
+
+        let to_remove: Vec<Entity> = <Entity>::query()
+            .iter(&self.ecs)
+            .filter(|entity| !keep.contains(entity))
+            .copied()
+            .collect();
+
+        to_remove.iter().for_each(|entity| {
+            self.ecs.remove(*entity);
+        });
+
+        let depth = self.resources.get::<Depth>().unwrap().next();
+        self.resources.insert(depth);
+        self.build_level();
+    }
+
+    /// Generate a map for the current depth, move the player onto it and populate it
+    fn build_level(&mut self) {
+        let depth = *self.resources.get::<Depth>().unwrap();
+
+        let mut rng = RandomNumberGenerator::seeded(self.options.level_seed(&depth));
+        // monsters draw from their own generator so their choices don't shift the levels
+        self.resources.insert(RandomNumberGenerator::seeded(
+            self.options.monster_seed(&depth),
+        ));
+        let mut camera = Camera::new();
+        let map_builder = MapBuilder::build(&mut rng, &self.options, &depth);
+
+        <(&mut Point, &mut FieldOfView)>::query()
+            .filter(component::<Player>())
+            .for_each_mut(&mut self.ecs, |(pos, fov)| {
+                *pos = map_builder.player_start;
+                // the old visible tiles belong to the previous map
+                *fov = fov.clone_dirty();
+            });
+        camera.on_player_move(map_builder.player_start);
+
+        if depth.is_final() {
+            spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
+        }
+
+        let fov = self.options.monster_fov;
+        let table = SpawnTable::for_level(&self.templates, &depth, map_builder.theme.kind());
+        // guaranteed entries take the first spawn points, the rest is left to chance.
+        // A monster spawned in a region is joined by a few more of its kind
+        let mut guaranteed = table.guaranteed();
+        for pos in map_builder.monster_spawns.iter() {
+            let template = match guaranteed.next() {
+                Some(template) => template,
+                None => match table.roll(&mut rng) {
+                    Some(template) => template,
+                    None => continue,
+                },
+            };
+
+            spawner::spawn_template(&mut self.ecs, &mut rng, template, *pos, fov);
+            if let TemplateKind::Monster { .. } = template.kind {
+                for companion in map_builder.companions(&mut rng, *pos) {
+                    spawner::spawn_template(&mut self.ecs, &mut rng, template, companion, fov);
+                }
+            }
+        }
+        for (pos, name) in map_builder.region_spawns {
+            let table = SpawnTable::named(&self.templates, name, &depth);
+            spawner::spawn_entity(&mut self.ecs, &mut rng, &table, pos, fov);
+        }
+
+        if let Some(mut game_log) = self.resources.get_mut::<GameLog>() {
+            game_log.push(format!("You enter depth {}", depth.0), YELLOW);
+            game_log.push(format!("(dug by the {})", map_builder.architect), GRAY);
+        }
 
         // initial turn state resource
         self.resources.insert(TurnState::AwaitingInput);
+        self.resources.insert(None::<Targeting>);
         self.resources.insert(map_builder.map);
         self.resources.insert(map_builder.theme);
         self.resources.insert(camera);
+        self.resources.insert(SpatialIndex::new());
+        self.resources.insert(FlowFields::new());
+    }
+
+    /// Back to the game, the movement options changed in the menu hold on this level too
+    fn resume(&mut self) {
+        if let Some(mut map) = self.resources.get_mut::<Map>() {
+            map.set_steps(self.options.steps);
+        }
+        self.options.mode = GameMode::Play;
+    }
+
+    fn save(&mut self) {
+        self.status = Some(match save_game(&self.ecs, &self.resources, &self.options) {
+            Ok(()) => "Game saved".to_string(),
+            Err(e) => format!("Save failed, {}", e),
+        });
+
+        self.options.mode = GameMode::Menu;
+    }
+
+    /// Restores the save file, on failure the current game is left as it was
+    fn load(&mut self) {
+        match load_game(&mut self.ecs, &mut self.resources, &mut self.options) {
+            Ok(()) => self.status = Some("Game loaded".to_string()),
+            Err(e) => {
+                self.status = Some(format!("Load failed, {}", e));
+                self.options.mode = GameMode::Menu;
+            }
+        }
     }
 
     fn handle_main_input(&mut self, ctx: &mut BTerm) {
+        // restarting, saving and loading throw away a game, so they wait for the menu.
+        // Once the quest has ended there is nothing left to lose by starting over
+        let in_menu = matches!(self.options.mode, GameMode::Menu);
+        let ended = matches!(
+            self.resources.get::<TurnState>().map(|state| *state),
+            Some(TurnState::GameOver) | Some(TurnState::Victory)
+        );
         if let Some(key) = ctx.key {
             match key {
-                VirtualKeyCode::R | VirtualKeyCode::S => self.options.mode = GameMode::Restart,
+                VirtualKeyCode::R if in_menu || ended => self.options.mode = GameMode::Restart,
+                VirtualKeyCode::S if in_menu => self.options.mode = GameMode::Save,
+                VirtualKeyCode::L if in_menu => self.options.mode = GameMode::Load,
                 VirtualKeyCode::Q => self.options.mode = GameMode::Quit,
                 VirtualKeyCode::M => self.options.mode = GameMode::Menu,
-                VirtualKeyCode::P => self.options.mode = GameMode::Play,
+                VirtualKeyCode::P => self.resume(),
                 _ => {}
             }
         }
@@ -107,29 +269,77 @@ impl State {
         ctx.set_active_console(2);
         ctx.print_centered(5, "The Rusty Amulet");
         ctx.print(10, 7, "[P] Play / Resume");
-        ctx.print(10, 8, "[R] Save / Restart");
-        ctx.print(10, 9, "[Q] Quit");
-        ctx.print_color(10, 11, GREEN, BLACK, "Options");
+        ctx.print(10, 8, "[S] Save");
+        ctx.print(10, 9, "[L] Load");
+        ctx.print(10, 10, "[R] Restart");
+        ctx.print(10, 11, "[Q] Quit");
+        ctx.print_color(10, 13, GREEN, BLACK, "Options");
         ctx.print(
             12,
-            12,
+            14,
             format!("> [<, >] Max rooms: {}", self.options.max_rooms),
         );
         ctx.print(
             12,
-            13,
+            15,
             format!("> [[, ]] Room size: {}", self.options.room_size),
         );
         ctx.print(
             12,
-            14,
+            16,
             format!("> [-, +] Monster FOV: {}", self.options.monster_fov),
         );
         ctx.print(
             12,
-            15,
+            17,
             format!("> [;, '] Player FOV: {}", self.options.player_fov),
         );
+        ctx.print(12, 18, format!("> [N] Seed: {}", self.options.seed));
+        ctx.print(
+            12,
+            19,
+            format!(
+                "> [D] Debug: {}",
+                if self.options.debug { "on" } else { "off" }
+            ),
+        );
+
+        ctx.print(
+            12,
+            20,
+            format!(
+                "> [E] Movement: {}",
+                if self.options.steps.diagonals {
+                    "8-way"
+                } else {
+                    "4-way"
+                }
+            ),
+        );
+        ctx.print(
+            12,
+            21,
+            format!(
+                "> [9, 0] Diagonal cost: {:.1}",
+                self.options.steps.diagonal_cost
+            ),
+        );
+        ctx.print(
+            12,
+            22,
+            format!(
+                "> [C] Cut corners: {}",
+                if self.options.steps.cut_corners {
+                    "on"
+                } else {
+                    "off"
+                }
+            ),
+        );
+
+        if let Some(status) = &self.status {
+            ctx.print_color(10, 24, YELLOW, BLACK, status);
+        }
 
         self.options.handle_input(ctx);
     }
@@ -147,17 +357,27 @@ impl State {
 
     fn run_systems(&mut self, ctx: &mut BTerm) {
         // determine which systems to execute bases on the current turn state
-        let state = self.resources.get::<TurnState>().unwrap().clone();
+        let state = *self.resources.get::<TurnState>().unwrap();
         match state {
             TurnState::AwaitingInput => self
                 .input_systems
                 .execute(&mut self.ecs, &mut self.resources),
+            TurnState::ShowingInventory | TurnState::DroppingItem => self
+                .inventory_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::ShowingHistory => self
+                .history_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::Targeting => self
+                .targeting_systems
+                .execute(&mut self.ecs, &mut self.resources),
             TurnState::PlayerTurn => self
                 .player_systems
                 .execute(&mut self.ecs, &mut self.resources),
             TurnState::MonsterTurn => self
                 .monster_systems
                 .execute(&mut self.ecs, &mut self.resources),
+            TurnState::NextLevel => self.advance_level(),
             TurnState::GameOver => self.game_over(ctx),
             TurnState::Victory => self.victory(ctx),
         }
@@ -217,6 +437,7 @@ impl GameState for State {
         // set active console to fetch mouse input in correctly scaled form
         ctx.set_active_console(0);
         self.resources.insert(Point::from_tuple(ctx.mouse_pos()));
+        self.resources.insert(MouseClick(ctx.left_click));
 
         match self.options.mode {
             GameMode::Play => self.run_systems(ctx),
@@ -224,11 +445,31 @@ impl GameState for State {
             GameMode::Menu => self.show_menu(ctx),
             GameMode::Quit => ctx.quitting = true,
             GameMode::Restart => self.restart(),
+            GameMode::Save => self.save(),
+            GameMode::Load => self.load(),
         }
     }
 }
 
 fn main() -> BError {
+    let args: Vec<String> = std::env::args().collect();
+    let options = GameOptions::from_args(&args);
+
+    // headless mode, only generate the levels and print them
+    if args.iter().any(|arg| arg == "--dump-map") {
+        dump_levels(&options);
+        return Ok(());
+    }
+
+    // a broken template file is reported before any window opens
+    let templates = match Templates::load() {
+        Ok(templates) => templates,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     let font = "dungeonfont.png";
     let term_font = "terminal8x8.png";
 
@@ -245,8 +486,13 @@ fn main() -> BError {
         .with_simple_console_no_bg(SCREEN_WIDTH * 2, SCREEN_HEIGHT * 2, term_font) // smaller hud font
         .build()?;
 
-    let mut state = State::new();
+    let mut state = State::new(options, templates);
     state.restart();
 
+    // pick up where the last session left off
+    if Path::new(SAVE_FILE).exists() {
+        state.load();
+    }
+
     main_loop(context, state)
 }
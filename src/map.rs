@@ -2,17 +2,92 @@
 use crate::prelude::*;
 
 const NUM_TILES: usize = (SCREEN_HEIGHT * SCREEN_WIDTH) as usize;
+/// opening a door takes a turn before stepping through, paths make it cost as much
+const DOOR_PATH_COST: f32 = 2.0;
+/// wading and scrambling over rubble are slow going, paths go around when they can
+const ROUGH_PATH_COST: f32 = 2.0;
+/// lava burns, so paths only lead through it when there is no other way
+const LAVA_PATH_COST: f32 = 10.0;
 
-#[derive(Copy, Clone, PartialEq)]
+const ORTHOGONAL_STEPS: [Point; 4] = [
+    Point::constant(-1, 0),
+    Point::constant(1, 0),
+    Point::constant(0, -1),
+    Point::constant(0, 1),
+];
+const ALL_STEPS: [Point; 8] = [
+    Point::constant(-1, 0),
+    Point::constant(1, 0),
+    Point::constant(0, -1),
+    Point::constant(0, 1),
+    Point::constant(-1, -1),
+    Point::constant(1, -1),
+    Point::constant(-1, 1),
+    Point::constant(1, 1),
+];
+
+/// Where a single step may lead, the player and monster paths follow the same rules
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StepRules {
+    /// eight-way movement, four-way without
+    pub diagonals: bool,
+    /// what a diagonal step costs when planning a path, a straight one costs 1.0
+    pub diagonal_cost: f32,
+    /// a diagonal step may squeeze past a wall on one side, never between two
+    pub cut_corners: bool,
+}
+
+impl Default for StepRules {
+    fn default() -> Self {
+        Self {
+            diagonals: false,
+            diagonal_cost: 1.4,
+            cut_corners: false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
+    Exit,
     Test,
+    DoorClosed,
+    DoorOpen,
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Rubble,
+    Grass,
 }
 
+impl TileType {
+    /// What stepping onto the tile costs a walker when planning a path, None when it can't.
+    /// Closed doors are included since they can be opened on the way
+    pub fn path_cost(self) -> Option<f32> {
+        match self {
+            TileType::Wall | TileType::DeepWater => None,
+            TileType::DoorClosed => Some(DOOR_PATH_COST),
+            TileType::ShallowWater | TileType::Rubble => Some(ROUGH_PATH_COST),
+            TileType::Lava => Some(LAVA_PATH_COST),
+            TileType::Floor
+            | TileType::Exit
+            | TileType::Test
+            | TileType::DoorOpen
+            | TileType::Grass => Some(1.0),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Map {
     pub tiles: Vec<TileType>,
     pub revealed_tiles: Vec<bool>,
+    /// bumped by every `set_tile` and `set_steps`, anything derived from the tiles can tell it
+    /// went stale
+    pub revision: u32,
+    pub steps: StepRules,
 }
 
 /// The map will use row-first encoding since we have a single dimension vector
@@ -29,11 +104,19 @@ pub fn map_idx(x: i32, y: i32) -> usize {
     ((y * SCREEN_WIDTH) + x) as usize
 }
 
+impl Default for Map {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Map {
     pub fn new() -> Self {
         Self {
             tiles: vec![TileType::Floor; NUM_TILES],
             revealed_tiles: vec![false; NUM_TILES],
+            revision: 0,
+            steps: StepRules::default(),
         }
     }
 
@@ -44,6 +127,15 @@ impl Map {
     pub fn set_tile(&mut self, point: Point, tile: TileType) {
         if let Some(idx) = self.try_idx(point) {
             self.tiles[idx] = tile;
+            self.revision = self.revision.wrapping_add(1);
+        }
+    }
+
+    /// Paths planned under the old rules no longer hold, so this counts as a change too
+    pub fn set_steps(&mut self, steps: StepRules) {
+        if self.steps != steps {
+            self.steps = steps;
+            self.revision = self.revision.wrapping_add(1);
         }
     }
 
@@ -51,18 +143,35 @@ impl Map {
         point.x >= 0 && point.x < SCREEN_WIDTH && point.y >= 0 && point.y < SCREEN_HEIGHT
     }
 
+    /// Nothing solid stands in the way, projectiles and blasts pass over water and lava
     pub fn can_enter_tile(&self, point: Point) -> bool {
         if let Some(idx) = self.try_idx(point) {
-            return match self.tiles[idx] {
-                TileType::Wall => false,
-                TileType::Floor | TileType::Test => true,
-
-            };
+            return !matches!(self.tiles[idx], TileType::Wall | TileType::DoorClosed);
         }
 
         false
     }
 
+    /// Whether a creature getting about with `locomotion` can step onto the tile,
+    /// only flyers and swimmers cross deep water.
+    /// Paths and the shared chase and flee fields are planned for walkers all the same, so
+    /// flyers and swimmers hunt and flee around deep water like anybody else and only end
+    /// up in it by wandering about. One field per way of getting about isn't worth it yet
+    pub fn can_cross(&self, point: Point, locomotion: Option<Locomotion>) -> bool {
+        match self.try_idx(point).map(|idx| self.tiles[idx]) {
+            Some(TileType::DeepWater) => locomotion.is_some(),
+            _ => self.can_enter_tile(point),
+        }
+    }
+
+    /// Somewhere to be put down without being asked, such as by a teleport.
+    /// Crossable, and no lava unless flying over it
+    pub fn is_safe(&self, point: Point, locomotion: Option<Locomotion>) -> bool {
+        let lava = self.try_idx(point)
+            .is_some_and(|idx| self.tiles[idx] == TileType::Lava);
+        self.can_cross(point, locomotion) && (!lava || locomotion == Some(Locomotion::Flying))
+    }
+
     pub fn try_idx(&self, point: Point) -> Option<usize> {
         if self.in_bounds(point) {
             Some(map_idx(point.x, point.y))
@@ -71,14 +180,51 @@ impl Map {
         }
     }
 
-    pub fn valid_exit(&self, loc: Point, delta: Point) -> Option<usize> {
-        let dest = loc + delta;
+    /// Closed doors are shut to movement, but whoever bumps into one opens it
+    pub fn is_closed_door(&self, point: Point) -> bool {
+        self.try_idx(point)
+            .is_some_and(|idx| self.tiles[idx] == TileType::DoorClosed)
+    }
 
-        if self.can_enter_tile(dest) {
-            Some(self.point2d_to_index(dest))
+    /// The directions a single step may take under the step rules
+    pub fn step_deltas(&self) -> &'static [Point] {
+        if self.steps.diagonals {
+            &ALL_STEPS
         } else {
-            None
+            &ORTHOGONAL_STEPS
+        }
+    }
+
+    /// Whether `to` is a single step away from `from` under the step rules, whatever is
+    /// standing there. Diagonal steps mind the corners they pass
+    pub fn is_step(&self, from: Point, to: Point) -> bool {
+        let delta = to - from;
+        if !self.step_deltas().contains(&delta) {
+            return false;
+        }
+        if delta.x == 0 || delta.y == 0 {
+            return true;
         }
+
+        let corners = [Point::new(from.x + delta.x, from.y), Point::new(from.x, from.y + delta.y)];
+        let open = corners.iter().filter(|corner| self.can_enter_tile(**corner)).count();
+        open == 2 || (self.steps.cut_corners && open == 1)
+    }
+
+    /// Paths are planned for walkers, costing each step by the terrain stepped onto
+    pub fn valid_exit(&self, loc: Point, delta: Point) -> Option<(usize, f32)> {
+        let dest = loc + delta;
+        let idx = self.try_idx(dest)?;
+        if !self.is_step(loc, dest) {
+            return None;
+        }
+
+        let diagonal = if delta.x != 0 && delta.y != 0 {
+            self.steps.diagonal_cost
+        } else {
+            1.0
+        };
+        self.tiles[idx].path_cost().map(|cost| (idx, cost * diagonal))
     }
 
     pub fn points_further_than(&self, start: Point, further_than: f32) -> Vec<Point> {
@@ -112,7 +258,7 @@ impl Map {
         let dijkstra_map =
             DijkstraMap::new(
                 SCREEN_WIDTH, SCREEN_HEIGHT,
-                &vec![p_idx], self, 1024.0);
+                &[p_idx], self, 1024.0);
 
         // find the highest path in the map
         const UNREACHABLE: f32 = f32::MAX;
@@ -141,25 +287,18 @@ impl Algorithm2D for Map {
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        // cannot see through wallsx    x
-        self.tiles[idx] == TileType::Wall
+        // cannot see through walls or closed doors
+        matches!(self.tiles[idx], TileType::Wall | TileType::DoorClosed)
     }
 
     fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
         let mut exits = SmallVec::new();
         let location = self.index_to_point2d(idx);
 
-        if let Some(idx) = self.valid_exit(location, Point::new(-1, 0)) {
-            exits.push((idx, 1.0))
-        }
-        if let Some(idx) = self.valid_exit(location, Point::new(1, 0)) {
-            exits.push((idx, 1.0))
-        }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, -1)) {
-            exits.push((idx, 1.0))
-        }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, 1)) {
-            exits.push((idx, 1.0))
+        for delta in self.step_deltas() {
+            if let Some(exit) = self.valid_exit(location, *delta) {
+                exits.push(exit)
+            }
         }
 
         exits
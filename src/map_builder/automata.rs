@@ -1,6 +1,9 @@
 use crate::prelude::*;
 
-use super::MapArchitect;
+use super::{apply_pools, MapArchitect};
+
+/// caves often hold an underground lake or a lava flow
+const AUTOMATA_POOLS: usize = 4;
 
 pub struct CellularAutomataArchitect;
 
@@ -68,8 +71,6 @@ impl CellularAutomataArchitect {
 
 impl MapArchitect for CellularAutomataArchitect {
     fn new(&mut self, rng: &mut RandomNumberGenerator, options: &GameOptions) -> MapBuilder {
-        println!("Running CellularAutomataArchitect");
-
         let mut mb = MapBuilder::new();
 
         self.random_noise_map(rng, &mut mb.map);
@@ -80,8 +81,13 @@ impl MapArchitect for CellularAutomataArchitect {
         let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
         mb.player_start = self.find_closest_point_to(center, &mb.map).unwrap();
         mb.amulet_start = self.find_furthest_point_from(mb.player_start, &mb.map).unwrap();
-        mb.monster_spawns = mb.spawn_locations(mb.player_start.clone(), options, rng, DEFAULT_NUM_MONSTERS);
+        mb.monster_spawns = mb.spawn_locations(mb.player_start, options, rng, DEFAULT_NUM_MONSTERS);
+        apply_pools(&mut mb, rng, AUTOMATA_POOLS);
 
         mb
     }
+
+    fn name(&self) -> &'static str {
+        "CellularAutomataArchitect"
+    }
 }
@@ -0,0 +1,139 @@
+use super::MapArchitect;
+use crate::prelude::*;
+use std::cmp::{max, min};
+
+/// the smallest partition worth splitting off, a room of 3 plus a wall on either side
+const MIN_PARTITION: i32 = 5;
+
+/// A node of the partition tree, leaves hold a room each
+enum Partition {
+    Leaf(Rect),
+    Split(Box<Partition>, Box<Partition>),
+}
+
+pub struct BspArchitect;
+
+impl BspArchitect {
+    /// Keep cutting `area` in two, along its longer side, until every partition is meant for
+    /// a single room or is too small to cut again. Never loops, however many rooms are asked for
+    fn split(area: Rect, rooms: usize, rng: &mut RandomNumberGenerator) -> Partition {
+        let (width, height) = (area.width(), area.height());
+        let can_split_x = width >= MIN_PARTITION * 2;
+        let can_split_y = height >= MIN_PARTITION * 2;
+        if rooms < 2 || !(can_split_x || can_split_y) {
+            return Partition::Leaf(area);
+        }
+
+        let split_x = can_split_x && (!can_split_y || width >= height);
+        let (first, second) = if split_x {
+            let at = rng.range(area.x1 + MIN_PARTITION, area.x2 - MIN_PARTITION + 1);
+            (
+                Rect::with_exact(area.x1, area.y1, at, area.y2),
+                Rect::with_exact(at, area.y1, area.x2, area.y2),
+            )
+        } else {
+            let at = rng.range(area.y1 + MIN_PARTITION, area.y2 - MIN_PARTITION + 1);
+            (
+                Rect::with_exact(area.x1, area.y1, area.x2, at),
+                Rect::with_exact(area.x1, at, area.x2, area.y2),
+            )
+        };
+
+        Partition::Split(
+            Box::new(BspArchitect::split(first, rooms / 2, rng)),
+            Box::new(BspArchitect::split(second, rooms - rooms / 2, rng)),
+        )
+    }
+
+    /// Carve a room into every leaf, leaving a wall around it, and connect the two halves of
+    /// every split with a corridor. Returns the center of a room in the partition
+    fn carve(
+        builder: &mut MapBuilder,
+        partition: &Partition,
+        rng: &mut RandomNumberGenerator,
+        options: &GameOptions,
+    ) -> Point {
+        match partition {
+            Partition::Leaf(leaf) => {
+                let width = rng.range(3, min(options.room_size, leaf.width() - 2) + 1);
+                let height = rng.range(3, min(options.room_size, leaf.height() - 2) + 1);
+                let room = Rect::with_size(
+                    rng.range(leaf.x1 + 1, leaf.x2 - width),
+                    rng.range(leaf.y1 + 1, leaf.y2 - height),
+                    width,
+                    height,
+                );
+
+                room.for_each(|p| builder.map.set_tile(p, TileType::Floor));
+                builder.rooms.push(room);
+                room.center()
+            }
+            Partition::Split(first, second) => {
+                let from = BspArchitect::carve(builder, first, rng, options);
+                let to = BspArchitect::carve(builder, second, rng, options);
+                BspArchitect::apply_corridor(builder, rng, from, to);
+
+                if rng.range(0, 2) == 0 {
+                    from
+                } else {
+                    to
+                }
+            }
+        }
+    }
+
+    /// An L shaped corridor, bending one way or the other
+    fn apply_corridor(
+        builder: &mut MapBuilder,
+        rng: &mut RandomNumberGenerator,
+        from: Point,
+        to: Point,
+    ) {
+        let corner = if rng.range(0, 2) == 0 {
+            Point::new(to.x, from.y)
+        } else {
+            Point::new(from.x, to.y)
+        };
+
+        for leg in [(from, corner), (corner, to)] {
+            for x in min(leg.0.x, leg.1.x)..=max(leg.0.x, leg.1.x) {
+                for y in min(leg.0.y, leg.1.y)..=max(leg.0.y, leg.1.y) {
+                    builder.map.set_tile(Point::new(x, y), TileType::Floor);
+                }
+            }
+        }
+    }
+}
+
+impl MapArchitect for BspArchitect {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, options: &GameOptions) -> MapBuilder {
+        let mut builder = MapBuilder::new();
+
+        builder.map.fill(TileType::Wall);
+
+        let tree = BspArchitect::split(
+            Rect::with_size(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT),
+            options.max_rooms,
+            rng,
+        );
+        BspArchitect::carve(&mut builder, &tree, rng, options);
+
+        builder.player_start = builder.rooms[0].center();
+        builder.amulet_start = builder.map.find_most_distant_from(builder.player_start);
+        builder.place_doors();
+
+        // a monster in every room but the first, where the player starts
+        builder.monster_spawns = builder
+            .rooms
+            .iter()
+            .skip(1)
+            .map(|r| Point::new(rng.range(r.x1, r.x2), rng.range(r.y1, r.y2)))
+            .collect();
+
+        builder
+    }
+
+    fn name(&self) -> &'static str {
+        "BspArchitect"
+    }
+}
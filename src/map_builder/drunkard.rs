@@ -1,11 +1,12 @@
 use crate::prelude::*;
 
-use super::MapArchitect;
+use super::{apply_pools, MapArchitect};
 
 pub struct DrunkardArchitect {}
 
 const STAGGER_DISTANCE: usize = 400;
 const OPEN_PERCENTAGE: f32 = 33.33;
+const DRUNKARD_POOLS: usize = 3;
 
 impl DrunkardArchitect {
     fn drunkard(
@@ -14,7 +15,7 @@ impl DrunkardArchitect {
         map: &mut Map,
         rng: &mut RandomNumberGenerator,
     ) {
-        let mut drunkard_pos = start.clone();
+        let mut drunkard_pos = *start;
         let mut distance_staggered = 0;
 
         loop {
@@ -55,7 +56,7 @@ impl DrunkardArchitect {
             let dijsktra_map = DijkstraMap::new(
                 SCREEN_WIDTH,
                 SCREEN_HEIGHT,
-                &vec![map.point2d_to_index(center)],
+                &[map.point2d_to_index(center)],
                 map,
                 1024.0,
             );
@@ -72,7 +73,6 @@ impl DrunkardArchitect {
                 .count() as f32;
 
             let completed_percentage = open_tiles / (SCREEN_WIDTH * SCREEN_HEIGHT) as f32 * 100f32;
-            println!("Drunkard finished carving, {:?}%", completed_percentage);
 
             if completed_percentage >= OPEN_PERCENTAGE {
                 break;
@@ -83,7 +83,6 @@ impl DrunkardArchitect {
 
 impl MapArchitect for DrunkardArchitect {
     fn new(&mut self, rng: &mut RandomNumberGenerator, options: &GameOptions) -> MapBuilder {
-        println!("Running DrunkardArchitect");
         let mut mb = MapBuilder::new();
 
         mb.map.fill(TileType::Wall);
@@ -91,9 +90,14 @@ impl MapArchitect for DrunkardArchitect {
 
         self.carve_map(&mut mb.map, rng);
 
-        mb.monster_spawns = mb.spawn_locations(mb.player_start.clone(), options, rng, DEFAULT_NUM_MONSTERS);
-        mb.amulet_start = mb.map.find_most_distant_from(mb.player_start.clone());
+        mb.monster_spawns = mb.spawn_locations(mb.player_start, options, rng, DEFAULT_NUM_MONSTERS);
+        mb.amulet_start = mb.map.find_most_distant_from(mb.player_start);
+        apply_pools(&mut mb, rng, DRUNKARD_POOLS);
 
         mb
     }
+
+    fn name(&self) -> &'static str {
+        "DrunkardArchitect"
+    }
 }
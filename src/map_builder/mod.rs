@@ -1,17 +1,23 @@
 mod automata;
+mod bsp;
 mod empty;
 mod rooms;
 mod drunkard;
 mod prefab;
+mod pools;
 mod themes;
+mod voronoi;
 
 use crate::prelude::*;
 
 use prefab::*;
+use pools::apply_pools;
 use automata::CellularAutomataArchitect;
+use bsp::BspArchitect;
 use empty::EmptyArchitect;
 use rooms::RoomsArchitect;
 use drunkard::DrunkardArchitect;
+use voronoi::VoronoiArchitect;
 use themes::DungeonTheme;
 use themes::ForestTheme;
 use themes::BeachTheme;
@@ -19,12 +25,40 @@ use themes::BeachTheme;
 // legion resources have to be thread safe (thus constrained by send + sync)
 pub trait MapTheme: Send + Sync {
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType;
+
+    fn kind(&self) -> ThemeKind;
+}
+
+/// Themes are boxed trait objects, this identifies them in a save game
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ThemeKind {
+    Dungeon,
+    Beach,
+    Forest,
+}
+
+impl ThemeKind {
+    pub fn theme(&self) -> Box<dyn MapTheme> {
+        match self {
+            ThemeKind::Dungeon => DungeonTheme::new(),
+            ThemeKind::Beach => BeachTheme::new(),
+            ThemeKind::Forest => ForestTheme::new(),
+        }
+    }
 }
 
+#[allow(clippy::new_ret_no_self, clippy::wrong_self_convention)]
 trait MapArchitect {
     fn new(&mut self, rng: &mut RandomNumberGenerator, options: &GameOptions) -> MapBuilder;
+
+    fn name(&self) -> &'static str;
 }
 
+/// paths any longer than this are as good as unreachable
+pub(crate) const UNREACHABLE: f32 = 2000.0;
+/// the most monsters that join the one spawned for a region, making up its group
+const MAX_COMPANIONS: i32 = 2;
+
 pub enum TunnelType {
     Horizontal,
     Vertical,
@@ -37,6 +71,15 @@ pub struct MapBuilder {
     pub player_start: Point,
     pub amulet_start: Point,
     pub theme: Box<dyn MapTheme>,
+    /// the architect that laid out this map, useful when comparing generators
+    pub architect: &'static str,
+    /// the areas covered by prefabs that could be placed
+    pub prefabs: Vec<Rect>,
+    /// spawns asked for with `spawn_in_region`, each filled from the named spawn table
+    pub region_spawns: Vec<(Point, &'static str)>,
+    /// the floor of every cell the architect split the map into, each gets a group of
+    /// monsters rather than monsters scattered over the whole map
+    pub regions: Vec<Vec<Point>>,
 }
 
 impl MapBuilder {
@@ -48,29 +91,76 @@ impl MapBuilder {
             player_start: Point::zero(),
             amulet_start: Point::zero(),
             theme: DungeonTheme::new(),
+            architect: "",
+            prefabs: Vec::new(),
+            region_spawns: Vec::new(),
+            regions: Vec::new(),
         }
     }
 
-    pub fn build(rng: &mut RandomNumberGenerator, options: &GameOptions) -> Self {
-        let mut architect: Box<dyn MapArchitect> = match rng.range(0, 3) {
-            0 => Box::new(RoomsArchitect{}),
-            1 => Box::new(CellularAutomataArchitect{}),
-            2 => Box::new(DrunkardArchitect{}),
-            _ => Box::new(EmptyArchitect{})
-        };
+    pub fn build(rng: &mut RandomNumberGenerator, options: &GameOptions, depth: &Depth) -> Self {
+        loop {
+            let mut architect: Box<dyn MapArchitect> = match rng.range(0, 5) {
+                0 => Box::new(RoomsArchitect{}),
+                1 => Box::new(CellularAutomataArchitect{}),
+                2 => Box::new(DrunkardArchitect{}),
+                3 => Box::new(BspArchitect{}),
+                4 => Box::new(VoronoiArchitect{}),
+                _ => Box::new(EmptyArchitect{})
+            };
 
-        let mut builder = architect.new(rng, options);
+            let mut builder = architect.new(rng, options);
+            builder.architect = architect.name();
+            builder.map.steps = options.steps;
 
-        apply_prefab(&mut builder, rng, &FORTRESS);
-        apply_prefab(&mut builder, rng, &SPIRALL);
+            apply_prefab(&mut builder, rng, &FORTRESS);
+            apply_prefab(&mut builder, rng, &SPIRALL);
 
-        builder.theme = match rng.range(0, 3) {
-            0 => DungeonTheme::new(),
-            1 => BeachTheme::new(),
-            _ => ForestTheme::new(),
-        };
+            // a vault can wall off whatever the architect had in mind, so the amulet or the
+            // stairs only go down once the prefabs are in. Start over when nothing is left to reach
+            builder.amulet_start = match builder.furthest_reachable() {
+                Some(pt) => pt,
+                None => continue,
+            };
+            // whatever was spawned or grouped before it can't share its tile
+            let amulet_start = builder.amulet_start;
+            builder.monster_spawns.retain(|pt| *pt != amulet_start);
+            builder.region_spawns.retain(|(pt, _)| *pt != amulet_start);
+            builder.regions.iter_mut().for_each(|region| region.retain(|pt| *pt != amulet_start));
+
+            // only the final depth holds the amulet, stairs lead further down everywhere else
+            if !depth.is_final() {
+                builder.map.set_tile(builder.amulet_start, TileType::Exit);
+            }
+
+            builder.theme = match rng.range(0, 3) {
+                0 => DungeonTheme::new(),
+                1 => BeachTheme::new(),
+                _ => ForestTheme::new(),
+            };
+
+            return builder;
+        }
+    }
+
+    /// the floor tile furthest along a path from the player start,
+    /// None when the player can't get anywhere
+    fn furthest_reachable(&self) -> Option<Point> {
+        let start = self.map.try_idx(self.player_start)?;
+        // the player may have ended up inside a vault wall
+        self.map.tiles[start].path_cost()?;
+
+        let dijkstra_map = DijkstraMap::new(
+            SCREEN_WIDTH, SCREEN_HEIGHT,
+            &[start], &self.map, 1024.0);
 
-        builder
+        dijkstra_map.map.iter()
+            .enumerate()
+            .filter(|(idx, dist)| *idx != start
+                && **dist < UNREACHABLE
+                && self.map.tiles[*idx] == TileType::Floor)
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
     }
 
     /// collect locations of possible spawn locations
@@ -82,10 +172,115 @@ impl MapBuilder {
 
         for _ in 0..num_monsters {
             let target_index = rng.random_slice_index(&spawn_locations).unwrap();
-            spawns.push(spawn_locations[target_index].clone());
+            spawns.push(spawn_locations[target_index]);
             spawn_locations.remove(target_index);
         }
 
         spawns
     }
+
+    /// one spawn location in every region out of the player's sight,
+    /// the rest of the region's group gathers around it with `companions`
+    pub fn group_spawns(&self, start: Point, options: &GameOptions, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+        let too_close = (options.player_fov + 2) as f32;
+
+        self.regions.iter()
+            .filter_map(|region| {
+                let far: Vec<&Point> = region.iter()
+                    .filter(|pt| DistanceAlg::Pythagoras.distance2d(start, **pt) > too_close)
+                    .filter(|pt| **pt != self.amulet_start)
+                    .collect();
+                rng.random_slice_entry(&far).map(|pt| **pt)
+            })
+            .collect()
+    }
+
+    /// free floor closest to `leader` in the leader's region, for the monsters that
+    /// join it. Nothing when the map wasn't split into regions
+    pub fn companions(&self, rng: &mut RandomNumberGenerator, leader: Point) -> Vec<Point> {
+        let region = match self.regions.iter().find(|region| region.contains(&leader)) {
+            Some(region) => region,
+            None => return Vec::new(),
+        };
+
+        let mut free: Vec<Point> = region.iter()
+            .filter(|pt| **pt != leader
+                && **pt != self.player_start
+                && **pt != self.amulet_start
+                && !self.monster_spawns.contains(pt)
+                && !self.region_spawns.iter().any(|(taken, _)| taken == *pt))
+            .copied()
+            .collect();
+        free.sort_by(|a, b| {
+            DistanceAlg::Pythagoras.distance2d(leader, *a)
+                .partial_cmp(&DistanceAlg::Pythagoras.distance2d(leader, *b))
+                .unwrap()
+        });
+        free.truncate(rng.range(0, MAX_COMPANIONS + 1) as usize);
+
+        free
+    }
+
+    /// A corridor tile right outside a room, squeezed between two walls, is a doorway
+    fn is_doorway(&self, pt: Point, along: Point) -> bool {
+        let wall = |p: Point| self.map.try_idx(p)
+            .is_some_and(|idx| self.map.tiles[idx] == TileType::Wall);
+
+        self.map.tiles[self.map.point2d_to_index(pt)] == TileType::Floor
+            && wall(pt - along)
+            && wall(pt + along)
+            && pt != self.player_start
+            && pt != self.amulet_start
+    }
+
+    /// Doorways where corridors meet the rooms get a closed door
+    pub fn place_doors(&mut self) {
+        let mut doors = Vec::new();
+
+        for room in self.rooms.iter() {
+            // the ring of tiles around the room, doorways on the sides run up and down
+            for x in room.x1..room.x2 {
+                for pt in [Point::new(x, room.y1 - 1), Point::new(x, room.y2)] {
+                    if self.map.in_bounds(pt) && self.is_doorway(pt, Point::new(1, 0)) {
+                        doors.push(pt);
+                    }
+                }
+            }
+            for y in room.y1..room.y2 {
+                for pt in [Point::new(room.x1 - 1, y), Point::new(room.x2, y)] {
+                    if self.map.in_bounds(pt) && self.is_doorway(pt, Point::new(0, 1)) {
+                        doors.push(pt);
+                    }
+                }
+            }
+        }
+
+        doors.into_iter().for_each(|pt| self.map.set_tile(pt, TileType::DoorClosed));
+    }
+
+    /// spawn `count` entities from the spawn table named `table` on free floor
+    /// inside `region`, returns how many could be placed
+    pub fn spawn_in_region(&mut self, rng: &mut RandomNumberGenerator, table: &'static str, region: Rect, count: usize) -> usize {
+        let mut free: Vec<Point> = Vec::new();
+        region.for_each(|pt| {
+            if self.map.in_bounds(pt)
+                && self.map.tiles[self.map.point2d_to_index(pt)] == TileType::Floor
+                && pt != self.player_start
+                && pt != self.amulet_start
+                && !self.monster_spawns.contains(&pt)
+                && !self.region_spawns.iter().any(|(taken, _)| *taken == pt)
+            {
+                free.push(pt);
+            }
+        });
+
+        let mut placed = 0;
+        while placed < count && !free.is_empty() {
+            let target_index = rng.random_slice_index(&free).unwrap();
+            self.region_spawns.push((free.remove(target_index), table));
+            placed += 1;
+        }
+
+        placed
+    }
 }
@@ -0,0 +1,103 @@
+use crate::prelude::*;
+
+const MAX_POOL_ATTEMPTS: i32 = 20;
+/// one pool in this many is lava, the rest are water
+const LAVA_ODDS: i32 = 4;
+
+/// Scatter up to `count` pools over the floor, deep water ringed by shallows and grass or
+/// lava ringed by rubble. A pool that would cut a walker off from part of the level is
+/// taken out again, monsters that would spawn in deep water or lava are dropped
+pub fn apply_pools(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator, count: usize) {
+    let mut placed = 0;
+    let mut attempts = 0;
+
+    while placed < count && attempts < MAX_POOL_ATTEMPTS {
+        attempts += 1;
+
+        let center = Point::new(
+            rng.range(1, SCREEN_WIDTH - 1),
+            rng.range(1, SCREEN_HEIGHT - 1),
+        );
+        if mb.map.tiles[mb.map.point2d_to_index(center)] != TileType::Floor {
+            continue;
+        }
+
+        let radius = rng.range(2, 5) as f32;
+        let (core, rim, shore) = if rng.range(0, LAVA_ODDS) == 0 {
+            (TileType::Lava, TileType::Rubble, TileType::Floor)
+        } else {
+            (TileType::DeepWater, TileType::ShallowWater, TileType::Grass)
+        };
+
+        let before = safe_to_reach(mb);
+        let untouched = mb.map.tiles.clone();
+
+        Rect::with_size(
+            center.x - radius as i32 - 1,
+            center.y - radius as i32 - 1,
+            radius as i32 * 2 + 3,
+            radius as i32 * 2 + 3,
+        )
+        .for_each(|pt| {
+            if !mb.map.in_bounds(pt)
+                || mb.map.tiles[mb.map.point2d_to_index(pt)] != TileType::Floor
+                || pt == mb.player_start
+                || pt == mb.amulet_start
+            {
+                return;
+            }
+
+            // a ragged edge looks more natural than a perfect circle
+            let distance =
+                DistanceAlg::Pythagoras.distance2d(center, pt) + rng.range(0, 10) as f32 / 10.0;
+            if distance < radius - 1.0 {
+                mb.map.set_tile(pt, core);
+            } else if distance < radius {
+                mb.map.set_tile(pt, rim);
+            } else if distance < radius + 1.0 {
+                mb.map.set_tile(pt, shore);
+            }
+        });
+
+        let after = safe_to_reach(mb);
+        let cut_off = (0..mb.map.tiles.len())
+            .any(|idx| before[idx] && !after[idx] && !is_hazard(mb.map.tiles[idx]));
+        if cut_off {
+            mb.map.tiles = untouched;
+        } else {
+            placed += 1;
+        }
+    }
+
+    let map = &mb.map;
+    mb.monster_spawns
+        .retain(|pt| !is_hazard(map.tiles[map.point2d_to_index(*pt)]));
+}
+
+/// Deep water can't be waded through and nobody should have to walk through lava
+fn is_hazard(tile: TileType) -> bool {
+    matches!(tile, TileType::DeepWater | TileType::Lava)
+}
+
+/// The tiles a walker can get to from the player start without crossing a hazard
+fn safe_to_reach(mb: &MapBuilder) -> Vec<bool> {
+    let mut safe = mb.map.clone();
+    safe.tiles
+        .iter_mut()
+        .filter(|tile| is_hazard(**tile))
+        .for_each(|tile| *tile = TileType::Wall);
+
+    let dijkstra_map = DijkstraMap::new(
+        SCREEN_WIDTH,
+        SCREEN_HEIGHT,
+        &[safe.point2d_to_index(mb.player_start)],
+        &safe,
+        1024.0,
+    );
+
+    dijkstra_map
+        .map
+        .iter()
+        .map(|dist| *dist < f32::MAX)
+        .collect()
+}
@@ -1,15 +1,18 @@
 use crate::prelude::*;
 
 const MAX_PLACEMENT_ATTEMPTS: i32 = 10;
+/// every placed prefab hides some loot, picked from this spawn table
+const VAULT_TABLE: &str = "vault";
+const VAULT_LOOT: usize = 2;
 
-/// A fortress, m for monster, - for space and # for a wall
+/// A fortress, m for monster, - for space, # for a wall and D for a door
 pub const FORTRESS: (&str, i32, i32) = ("
 ------------
 ---######---
 ---#----#---
 ---#-M--#---
 -###----###-
--EM------ME-
+-EDM----MDE-
 -###----###-
 ---#----#---
 ---#----#---
@@ -35,7 +38,7 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator, vault:
     let dijksta_map = DijkstraMap::new(
         SCREEN_WIDTH,
         SCREEN_HEIGHT,
-        &vec![mb.map.point2d_to_index(mb.player_start)],
+        &[mb.map.point2d_to_index(mb.player_start)],
         &mb.map,
         1024.0
     );
@@ -84,12 +87,15 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator, vault:
             x += 1;
         });
 
-        if can_place {
+        if can_place && !cuts_off(mb, &dijksta_map, vault, random_rect) {
             placement = Some(Point::new(random_rect.x1, random_rect.y1));
+            mb.prefabs.push(random_rect);
             let vault_points = random_rect.point_set();
 
             // ensure we do not overwrite monsters
             mb.monster_spawns.retain(|pt| !vault_points.contains(pt));
+            // nor send a region's group into the vault
+            mb.regions.iter_mut().for_each(|region| region.retain(|pt| !vault_points.contains(pt)));
         }
 
         attempts += 1;
@@ -97,30 +103,60 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator, vault:
 
     if let Some(pt) = placement {
         //println!("Placing vault at: {:?}", pt);
+        let monsters = stamp(&mut mb.map, vault, pt);
+        mb.monster_spawns.extend(monsters);
 
-        let mut x = pt.x;
-        let mut y = pt.y;
+        mb.spawn_in_region(rng, VAULT_TABLE, Rect::with_size(pt.x, pt.y, vault.1, vault.2), VAULT_LOOT);
+    }
+}
 
-        vault.0.chars().into_iter().for_each(|c| {
-            let delta = Point::new(x, y);
-            match c {
-                '-' | 'E' => mb.map.set_tile(delta, TileType::Floor),
-                '#' => mb.map.set_tile(delta, TileType::Wall),
-                'M' => {
-                    // might have been another tile
-                    mb.map.set_tile(delta, TileType::Floor);
-                    mb.monster_spawns.push(delta);
-                },
-                '\n' => {
-                    x = pt.x;
-                    y += 1;
-                },
-                //'E' => mb.map.set_tile(delta, TileType::Test),
-                _ => println!("Cannot place prefab piece: {:?}", c)
-            }
+/// Write the vault onto the map with its top left corner at `pt`,
+/// returns where its monsters should spawn
+fn stamp(map: &mut Map, vault: &(&str, i32, i32), pt: Point) -> Vec<Point> {
+    let mut monsters = Vec::new();
+    let mut x = pt.x;
+    let mut y = pt.y;
+
+    vault.0.chars().for_each(|c| {
+        let delta = Point::new(x, y);
+        match c {
+            '-' | 'E' => map.set_tile(delta, TileType::Floor),
+            '#' => map.set_tile(delta, TileType::Wall),
+            'D' => map.set_tile(delta, TileType::DoorClosed),
+            'M' => {
+                // might have been another tile
+                map.set_tile(delta, TileType::Floor);
+                monsters.push(delta);
+            },
+            '\n' => {
+                x = pt.x;
+                y += 1;
+            },
+            //'E' => map.set_tile(delta, TileType::Test),
+            _ => println!("Cannot place prefab piece: {:?}", c)
+        }
 
-            x += 1;
-        });
-    }
+        x += 1;
+    });
+
+    monsters
 }
 
+/// Whether the vault's walls would cut the player off from floor outside of it
+/// that could be reached before, say by running across the only corridor to a room
+fn cuts_off(mb: &MapBuilder, before: &DijkstraMap, vault: &(&str, i32, i32), rect: Rect) -> bool {
+    let mut map = mb.map.clone();
+    stamp(&mut map, vault, Point::new(rect.x1, rect.y1));
+
+    let after = DijkstraMap::new(
+        SCREEN_WIDTH,
+        SCREEN_HEIGHT,
+        &[map.point2d_to_index(mb.player_start)],
+        &map,
+        1024.0
+    );
+
+    (0..map.tiles.len()).any(|idx| before.map[idx] < UNREACHABLE
+        && after.map[idx] >= UNREACHABLE
+        && !rect.point_in_rect(map.index_to_point2d(idx)))
+}
@@ -55,7 +55,7 @@ impl RoomsArchitect {
 
     fn build_corridors(builder: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
         let mut rooms = builder.rooms.clone();
-        rooms.sort_by(|a, b| a.center().x.cmp(&b.center().x));
+        rooms.sort_by_key(|r| r.center().x);
 
         for (i, room) in rooms.iter().enumerate().skip(1) {
             let prev = rooms[i - 1].center();
@@ -74,7 +74,6 @@ impl RoomsArchitect {
 
 impl MapArchitect for RoomsArchitect {
     fn new(&mut self, rng: &mut RandomNumberGenerator, options: &GameOptions) -> MapBuilder {
-        println!("Running RoomsArchitect");
         let mut builder = MapBuilder::new();
 
         builder.map.fill(TileType::Wall);
@@ -84,6 +83,7 @@ impl MapArchitect for RoomsArchitect {
 
         builder.player_start = builder.rooms[0].center();
         builder.amulet_start = builder.map.find_most_distant_from(builder.player_start);
+        builder.place_doors();
 
         // create monsters
         builder.monster_spawns = builder
@@ -99,4 +99,8 @@ impl MapArchitect for RoomsArchitect {
 
         builder
     }
+
+    fn name(&self) -> &'static str {
+        "RoomsArchitect"
+    }
 }
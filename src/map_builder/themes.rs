@@ -5,18 +5,21 @@ pub struct ForestTheme {}
 pub struct BeachTheme {}
 
 impl DungeonTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self{})
     }
 }
 
 impl BeachTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self{})
     }
 }
 
 impl ForestTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self{})
     }
@@ -27,9 +30,21 @@ impl MapTheme for DungeonTheme {
         match tile_type {
             TileType::Wall => to_cp437('q'),
             TileType::Floor => to_cp437('p'),
+            TileType::Exit => to_cp437('>'),
             TileType::Test => to_cp437('T'),
+            TileType::DoorClosed => to_cp437('+'),
+            TileType::DoorOpen => to_cp437('\''),
+            TileType::ShallowWater => to_cp437('~'),
+            TileType::DeepWater => to_cp437('6'),
+            TileType::Lava => to_cp437('='),
+            TileType::Rubble => to_cp437(':'),
+            TileType::Grass => to_cp437(','),
         }
     }
+
+    fn kind(&self) -> ThemeKind {
+        ThemeKind::Dungeon
+    }
 }
 
 impl MapTheme for BeachTheme {
@@ -37,9 +52,21 @@ impl MapTheme for BeachTheme {
         match tile_type {
             TileType::Wall => to_cp437('6'),
             TileType::Floor => to_cp437('7'),
+            TileType::Exit => to_cp437('>'),
             TileType::Test => to_cp437('T'),
+            TileType::DoorClosed => to_cp437('+'),
+            TileType::DoorOpen => to_cp437('\''),
+            TileType::ShallowWater => to_cp437('~'),
+            TileType::DeepWater => to_cp437('6'),
+            TileType::Lava => to_cp437('='),
+            TileType::Rubble => to_cp437(';'),
+            TileType::Grass => to_cp437(','),
         }
     }
+
+    fn kind(&self) -> ThemeKind {
+        ThemeKind::Beach
+    }
 }
 
 impl MapTheme for ForestTheme {
@@ -47,7 +74,19 @@ impl MapTheme for ForestTheme {
         match tile_type {
             TileType::Wall => to_cp437('"'),
             TileType::Floor => to_cp437(';'),
+            TileType::Exit => to_cp437('>'),
             TileType::Test => to_cp437('T'),
+            TileType::DoorClosed => to_cp437('+'),
+            TileType::DoorOpen => to_cp437('\''),
+            TileType::ShallowWater => to_cp437('~'),
+            TileType::DeepWater => to_cp437('6'),
+            TileType::Lava => to_cp437('='),
+            TileType::Rubble => to_cp437(':'),
+            TileType::Grass => to_cp437(','),
         }
     }
+
+    fn kind(&self) -> ThemeKind {
+        ThemeKind::Forest
+    }
 }
@@ -0,0 +1,150 @@
+use super::MapArchitect;
+use crate::prelude::*;
+use std::collections::BTreeMap;
+
+/// how many cells the hive is split into
+const VORONOI_SEEDS: usize = 24;
+/// cells with less floor than this are too cramped to hold a group of monsters
+const MIN_REGION: usize = 6;
+
+pub struct VoronoiArchitect;
+
+impl VoronoiArchitect {
+    /// The seed nearest to every tile of the map, the tiles sharing a seed make up a cell
+    fn membership(seeds: &[Point]) -> Vec<usize> {
+        (0..SCREEN_WIDTH * SCREEN_HEIGHT)
+            .map(|idx| {
+                let pt = Point::new(idx % SCREEN_WIDTH, idx / SCREEN_WIDTH);
+                seeds
+                    .iter()
+                    .enumerate()
+                    .map(|(region, seed)| {
+                        (region, DistanceAlg::PythagorasSquared.distance2d(pt, *seed))
+                    })
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                    .map(|(region, _)| region)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    /// Every tile whose neighbours all belong to its own cell is floor, leaving a wall
+    /// wherever two cells meet and around the edge of the map
+    fn carve_cells(map: &mut Map, membership: &[usize]) {
+        for y in 1..SCREEN_HEIGHT - 1 {
+            for x in 1..SCREEN_WIDTH - 1 {
+                let region = membership[map_idx(x, y)];
+                let inside = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .iter()
+                    .all(|(dx, dy)| membership[map_idx(x + dx, y + dy)] == region);
+                if inside {
+                    map.set_tile(Point::new(x, y), TileType::Floor);
+                }
+            }
+        }
+    }
+
+    /// Knock a hole through the wall between every pair of neighbouring cells. A hole is
+    /// two tiles, one from either cell, with floor on both sides of it
+    fn connect_cells(map: &mut Map, membership: &[usize], rng: &mut RandomNumberGenerator) {
+        // ordered by the pair of cells, so a seed always digs the same holes
+        let mut walls: BTreeMap<(usize, usize), Vec<(Point, Point)>> = BTreeMap::new();
+        let floor = |map: &Map, pt: Point| {
+            map.try_idx(pt)
+                .is_some_and(|idx| map.tiles[idx] == TileType::Floor)
+        };
+
+        for y in 1..SCREEN_HEIGHT - 1 {
+            for x in 1..SCREEN_WIDTH - 1 {
+                let from = Point::new(x, y);
+                for delta in [Point::new(1, 0), Point::new(0, 1)] {
+                    let to = from + delta;
+                    let (a, b) = (
+                        membership[map.point2d_to_index(from)],
+                        membership[map.point2d_to_index(to)],
+                    );
+                    if a != b && floor(map, from - delta) && floor(map, to + delta) {
+                        walls
+                            .entry((a.min(b), a.max(b)))
+                            .or_default()
+                            .push((from, to));
+                    }
+                }
+            }
+        }
+
+        for holes in walls.values() {
+            let (from, to) = holes[rng.random_slice_index(holes).unwrap()];
+            map.set_tile(from, TileType::Floor);
+            map.set_tile(to, TileType::Floor);
+        }
+    }
+}
+
+impl MapArchitect for VoronoiArchitect {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, options: &GameOptions) -> MapBuilder {
+        let mut mb = MapBuilder::new();
+
+        mb.map.fill(TileType::Wall);
+
+        let seeds: Vec<Point> = (0..VORONOI_SEEDS)
+            .map(|_| {
+                Point::new(
+                    rng.range(2, SCREEN_WIDTH - 2),
+                    rng.range(2, SCREEN_HEIGHT - 2),
+                )
+            })
+            .collect();
+        let membership = VoronoiArchitect::membership(&seeds);
+        VoronoiArchitect::carve_cells(&mut mb.map, &membership);
+        VoronoiArchitect::connect_cells(&mut mb.map, &membership, rng);
+
+        let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
+        mb.player_start = mb
+            .map
+            .distance_from_point(center)
+            .iter()
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(idx, _)| mb.map.index_to_point2d(*idx))
+            .unwrap_or(center);
+
+        // wall in cells a tiny or walled-off seed left without a way in
+        let dijkstra_map = DijkstraMap::new(
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+            &[mb.map.point2d_to_index(mb.player_start)],
+            &mb.map,
+            1024.0,
+        );
+        dijkstra_map
+            .map
+            .iter()
+            .enumerate()
+            .filter(|(_, dist)| **dist == f32::MAX)
+            .for_each(|(idx, _)| {
+                mb.map
+                    .set_tile(mb.map.index_to_point2d(idx), TileType::Wall)
+            });
+
+        let mut regions = vec![Vec::new(); VORONOI_SEEDS];
+        mb.map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, tile)| **tile == TileType::Floor)
+            .for_each(|(idx, _)| regions[membership[idx]].push(mb.map.index_to_point2d(idx)));
+        mb.regions = regions
+            .into_iter()
+            .filter(|region| region.len() >= MIN_REGION)
+            .collect();
+
+        mb.amulet_start = mb.map.find_most_distant_from(mb.player_start);
+        mb.monster_spawns = mb.group_spawns(mb.player_start, options, rng);
+
+        mb
+    }
+
+    fn name(&self) -> &'static str {
+        "VoronoiArchitect"
+    }
+}
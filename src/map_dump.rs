@@ -0,0 +1,119 @@
+use crate::map_builder::UNREACHABLE;
+use crate::prelude::*;
+
+/// Build every depth for the configured seed without opening a window,
+/// printing each map as ascii followed by some statistics
+pub fn dump_levels(options: &GameOptions) {
+    println!("Seed {}", options.seed);
+    println!("Legend: # wall, . floor, > exit, + and ' door, ~ and W shallow and deep water, L lava, : rubble, \" grass, @ player, A amulet, M monster, R region spawn, % and , prefab wall and floor");
+
+    for depth in 1..=FINAL_DEPTH {
+        let depth = Depth(depth);
+        let mut rng = RandomNumberGenerator::seeded(options.level_seed(&depth));
+        let builder = MapBuilder::build(&mut rng, options, &depth);
+
+        println!();
+        println!("Depth {} / {}", depth.0, FINAL_DEPTH);
+        print!("{}", render_ascii(&builder, &depth));
+        print_stats(&builder);
+    }
+}
+
+fn render_ascii(builder: &MapBuilder, depth: &Depth) -> String {
+    let mut ascii = String::with_capacity(((SCREEN_WIDTH + 1) * SCREEN_HEIGHT) as usize);
+
+    for y in 0..SCREEN_HEIGHT {
+        for x in 0..SCREEN_WIDTH {
+            let pt = Point::new(x, y);
+            let in_prefab = builder.prefabs.iter().any(|r| r.point_in_rect(pt));
+
+            let c = if pt == builder.player_start {
+                '@'
+            } else if pt == builder.amulet_start && depth.is_final() {
+                'A'
+            } else if builder.monster_spawns.contains(&pt) {
+                'M'
+            } else if builder.region_spawns.iter().any(|(spawn, _)| *spawn == pt) {
+                'R'
+            } else {
+                match builder.map.tiles[map_idx(x, y)] {
+                    TileType::Wall if in_prefab => '%',
+                    TileType::Wall => '#',
+                    TileType::Floor if in_prefab => ',',
+                    TileType::Floor => '.',
+                    TileType::Exit => '>',
+                    TileType::Test => 'T',
+                    TileType::DoorClosed => '+',
+                    TileType::DoorOpen => '\'',
+                    TileType::ShallowWater => '~',
+                    TileType::DeepWater => 'W',
+                    TileType::Lava => 'L',
+                    TileType::Rubble => ':',
+                    TileType::Grass => '"',
+                }
+            };
+
+            ascii.push(c);
+        }
+        ascii.push('\n');
+    }
+
+    ascii
+}
+
+fn print_stats(builder: &MapBuilder) {
+    let map = &builder.map;
+    let total = map.tiles.len();
+    let walkable = map
+        .tiles
+        .iter()
+        .filter(|tile| tile.path_cost().is_some())
+        .count();
+
+    let dijkstra_map = DijkstraMap::new(
+        SCREEN_WIDTH,
+        SCREEN_HEIGHT,
+        &[map.point2d_to_index(builder.player_start)],
+        map,
+        1024.0,
+    );
+    let reachable = dijkstra_map
+        .map
+        .iter()
+        .filter(|dist| **dist < UNREACHABLE)
+        .count();
+    let amulet_distance = dijkstra_map.map[map.point2d_to_index(builder.amulet_start)];
+
+    println!("Architect: {}", builder.architect);
+    println!(
+        "Floor: {} / {} tiles ({:.1}%)",
+        walkable,
+        total,
+        walkable as f32 / total as f32 * 100.0
+    );
+    println!(
+        "Reachable from player: {} tiles ({:.1}% of floor)",
+        reachable,
+        reachable as f32 / walkable.max(1) as f32 * 100.0
+    );
+    if amulet_distance < UNREACHABLE {
+        // weighted by doors, terrain and diagonals, so more than the number of steps
+        println!("Path cost to amulet / exit: {:.1}", amulet_distance);
+    } else {
+        println!("Path cost to amulet / exit: unreachable");
+    }
+    println!("Rooms: {}", builder.rooms.len());
+    println!("Regions: {}", builder.regions.len());
+    println!("Monster spawns: {}", builder.monster_spawns.len());
+    println!("Region spawns: {}", builder.region_spawns.len());
+    println!("Prefabs placed: {}", builder.prefabs.len());
+    builder.prefabs.iter().for_each(|r| {
+        println!(
+            "  at ({}, {}) size {}x{}",
+            r.x1,
+            r.y1,
+            r.width(),
+            r.height()
+        );
+    });
+}
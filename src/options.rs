@@ -4,21 +4,35 @@ pub const DEFAULT_NUM_MONSTERS: usize = 50;
 const DEFAULT_NUM_ROOMS: usize = 10;
 const DEFAULT_ROOM_SIZE: i32 = 10;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum GameMode {
     Play,
     Menu,
     Quit,
     Restart,
+    Save,
+    Load,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GameOptions {
     pub max_rooms: usize,
     pub room_size: i32,
     pub mode: GameMode,
     pub monster_fov: i32,
     pub player_fov: i32,
+    /// drives every random choice made while building a level and by the monsters
+    pub seed: u64,
+    /// shows the inner workings of the game on the HUD, such as whose turn it is
+    pub debug: bool,
+    /// four- or eight-way movement, handed to every level as it is built
+    pub steps: StepRules,
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameOptions {
@@ -29,27 +43,66 @@ impl GameOptions {
             mode: GameMode::Play,
             monster_fov: 6,
             player_fov: 8,
+            seed: RandomNumberGenerator::new().next_u64(),
+            debug: false,
+            steps: StepRules::default(),
+        }
+    }
+
+    /// Start from the defaults, overriding them from the command line, e.g. `--seed 1234 --debug`
+    /// or `--eight-way`
+    pub fn from_args(args: &[String]) -> Self {
+        let mut options = Self::new();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--seed" {
+                match args.next().map(|seed| seed.parse()) {
+                    Some(Ok(seed)) => options.seed = seed,
+                    _ => eprintln!("--seed expects a positive number, using {}", options.seed),
+                }
+            } else if arg == "--debug" {
+                options.debug = true;
+            } else if arg == "--eight-way" {
+                options.steps.diagonals = true;
+            }
         }
+
+        options
+    }
+
+    /// Every depth gets its own seed derived from the game seed, so a level
+    /// looks the same no matter how long the player took to reach it
+    pub fn level_seed(&self, depth: &Depth) -> u64 {
+        self.seed ^ (depth.0 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+    }
+
+    /// The monsters draw from a generator of their own, seeded like this whenever a level
+    /// is entered or loaded
+    pub fn monster_seed(&self, depth: &Depth) -> u64 {
+        !self.level_seed(depth)
     }
 
     pub fn handle_input(&mut self, ctx: &mut BTerm) {
         if let Some(key) = ctx.key {
             // TODO: use menu highlighting system or mouse
             match key {
-                VirtualKeyCode::LBracket if self.room_size > 4 => {
-                    self.room_size = self.room_size - 1
-                }
-                VirtualKeyCode::RBracket => self.room_size = self.room_size + 1,
-                VirtualKeyCode::Comma if self.max_rooms > 1 => self.max_rooms = self.max_rooms - 1,
-                VirtualKeyCode::Period => self.max_rooms = self.max_rooms + 1,
-                VirtualKeyCode::Equals => self.monster_fov = self.monster_fov + 1,
-                VirtualKeyCode::Minus if self.monster_fov > 3 => {
-                    self.monster_fov = self.monster_fov - 1
-                }
-                VirtualKeyCode::Apostrophe => self.player_fov = self.player_fov + 1,
-                VirtualKeyCode::Semicolon if self.player_fov > 4 => {
-                    self.player_fov = self.player_fov - 1
+                VirtualKeyCode::LBracket if self.room_size > 4 => self.room_size -= 1,
+                VirtualKeyCode::RBracket => self.room_size += 1,
+                VirtualKeyCode::Comma if self.max_rooms > 1 => self.max_rooms -= 1,
+                VirtualKeyCode::Period => self.max_rooms += 1,
+                VirtualKeyCode::Equals => self.monster_fov += 1,
+                VirtualKeyCode::Minus if self.monster_fov > 3 => self.monster_fov -= 1,
+                VirtualKeyCode::Apostrophe => self.player_fov += 1,
+                VirtualKeyCode::Semicolon if self.player_fov > 4 => self.player_fov -= 1,
+                VirtualKeyCode::N => self.seed = RandomNumberGenerator::new().next_u64(),
+                VirtualKeyCode::D => self.debug = !self.debug,
+                VirtualKeyCode::E => self.steps.diagonals = !self.steps.diagonals,
+                VirtualKeyCode::C => self.steps.cut_corners = !self.steps.cut_corners,
+                VirtualKeyCode::Key9 => {
+                    self.steps.diagonal_cost = f32::max(1.0, self.steps.diagonal_cost - 0.1)
                 }
+                VirtualKeyCode::Key0 => self.steps.diagonal_cost += 0.1,
                 _ => {}
             }
         }
@@ -0,0 +1,225 @@
+use crate::prelude::*;
+
+use legion::serialize::{Canon, UnknownType};
+use serde::de::DeserializeSeed;
+use std::fmt;
+use std::fs;
+
+/// Bump this whenever a component or resource changes shape, older saves are then rejected
+pub const SAVE_VERSION: u32 = 2;
+pub const SAVE_FILE: &str = "savegame.json";
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    Version(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "could not access {}: {}", SAVE_FILE, e),
+            SaveError::Format(e) => write!(f, "{} is corrupt: {}", SAVE_FILE, e),
+            SaveError::Version(found) => write!(
+                f,
+                "{} is version {}, only version {} is supported",
+                SAVE_FILE, found, SAVE_VERSION
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveError::Format(e)
+    }
+}
+
+/// Only the version is read first, so a save of a different shape never reaches the real parser
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveGame {
+    version: u32,
+    depth: Depth,
+    turn_state: TurnState,
+    options: GameOptions,
+    theme: ThemeKind,
+    camera: Camera,
+    game_log: GameLog,
+    map: Map,
+    world: serde_json::Value,
+}
+
+/// Every component that should survive a save needs to be registered here with a stable key
+fn registry() -> Registry<String> {
+    let mut registry = Registry::<String>::new(Canon::default());
+    // message entities carry a () marker, those are skipped
+    registry.on_unknown(UnknownType::Ignore);
+
+    registry.register::<Point>("point".to_string());
+    registry.register::<FieldOfView>("field_of_view".to_string());
+    registry.register::<Player>("player".to_string());
+    registry.register::<Enemy>("enemy".to_string());
+    registry.register::<MovesRandomly>("moves_randomly".to_string());
+    registry.register::<ChasingPlayer>("chasing_player".to_string());
+    registry.register::<BlocksTile>("blocks_tile".to_string());
+    registry.register::<AiState>("ai_state".to_string());
+    registry.register::<Faction>("faction".to_string());
+    registry.register::<Provoked>("provoked".to_string());
+    registry.register::<Charmed>("charmed".to_string());
+    registry.register::<ProvidesCharm>("provides_charm".to_string());
+    registry.register::<Render>("render".to_string());
+    registry.register::<WantsToMove>("wants_to_move".to_string());
+    registry.register::<Health>("health".to_string());
+    registry.register::<Name>("name".to_string());
+    registry.register::<Attack>("attack".to_string());
+    registry.register::<Defense>("defense".to_string());
+    registry.register::<WantsToAttack>("wants_to_attack".to_string());
+    registry.register::<ItemReceived>("item_received".to_string());
+    registry.register::<Item>("item".to_string());
+    registry.register::<AmuletOfYala>("amulet_of_yala".to_string());
+    registry.register::<Carried>("carried".to_string());
+    registry.register::<ActivateItem>("activate_item".to_string());
+    registry.register::<WantsToDrop>("wants_to_drop".to_string());
+    registry.register::<Consumable>("consumable".to_string());
+    registry.register::<ProvidesHealing>("provides_healing".to_string());
+    registry.register::<ProvidesDungeonMap>("provides_dungeon_map".to_string());
+    registry.register::<ProvidesTeleport>("provides_teleport".to_string());
+    registry.register::<ProvidesVision>("provides_vision".to_string());
+    registry.register::<VisionBoost>("vision_boost".to_string());
+    registry.register::<Energy>("energy".to_string());
+    registry.register::<Hasted>("hasted".to_string());
+    registry.register::<Slowed>("slowed".to_string());
+    registry.register::<ProvidesHaste>("provides_haste".to_string());
+    registry.register::<ProvidesSlow>("provides_slow".to_string());
+    registry.register::<Equippable>("equippable".to_string());
+    registry.register::<Equipped>("equipped".to_string());
+    registry.register::<AttackBonus>("attack_bonus".to_string());
+    registry.register::<DefenseBonus>("defense_bonus".to_string());
+    registry.register::<Experience>("experience".to_string());
+    registry.register::<ExperienceValue>("experience_value".to_string());
+    registry.register::<Poisoned>("poisoned".to_string());
+    registry.register::<Confused>("confused".to_string());
+    registry.register::<Asleep>("asleep".to_string());
+    registry.register::<InflictsStatus>("inflicts_status".to_string());
+    registry.register::<Trap>("trap".to_string());
+    registry.register::<Ranged>("ranged".to_string());
+    registry.register::<AreaOfEffect>("area_of_effect".to_string());
+    registry.register::<InflictsDamage>("inflicts_damage".to_string());
+    registry.register::<ProvidesBlink>("provides_blink".to_string());
+    registry.register::<Afraid>("afraid".to_string());
+    registry.register::<Mana>("mana".to_string());
+    registry.register::<Ability>("ability".to_string());
+    registry.register::<WantsToCast>("wants_to_cast".to_string());
+    registry.register::<Locomotion>("locomotion".to_string());
+
+    registry
+}
+
+/// Write the world and every resource needed to resume play to the save file
+pub fn save_game(
+    ecs: &World,
+    resources: &Resources,
+    options: &GameOptions,
+) -> Result<(), SaveError> {
+    let registry = registry();
+    let world = serde_json::to_value(ecs.as_serializable(any(), &registry))?;
+
+    let save = SaveGame {
+        version: SAVE_VERSION,
+        depth: *resources.get::<Depth>().unwrap(),
+        turn_state: *resources.get::<TurnState>().unwrap(),
+        options: options.clone(),
+        theme: resources.get::<Box<dyn MapTheme>>().unwrap().kind(),
+        camera: resources.get::<Camera>().unwrap().clone(),
+        game_log: resources.get::<GameLog>().unwrap().clone(),
+        map: resources.get::<Map>().unwrap().clone(),
+        world,
+    };
+
+    fs::write(SAVE_FILE, serde_json::to_string(&save)?)?;
+    Ok(())
+}
+
+/// Replace the world and resources with the contents of the save file,
+/// nothing is touched unless the whole file could be read
+pub fn load_game(
+    ecs: &mut World,
+    resources: &mut Resources,
+    options: &mut GameOptions,
+) -> Result<(), SaveError> {
+    let save = parse_save(&fs::read_to_string(SAVE_FILE)?)?;
+    *ecs = registry().as_deserialize().deserialize(save.world)?;
+
+    resources.insert(save.depth);
+    // the shot being lined up is not saved, the player can simply take aim again
+    resources.insert(match save.turn_state {
+        TurnState::Targeting => TurnState::AwaitingInput,
+        turn_state => turn_state,
+    });
+    resources.insert(None::<Targeting>);
+    resources.insert(save.theme.theme());
+    resources.insert(save.camera);
+    resources.insert(save.game_log);
+    resources.insert(save.map);
+    // the loaded map may differ from the current one without a newer revision
+    resources.insert(FlowFields::new());
+    // the generator state itself can't be stored, reseed it the way entering the level does
+    resources.insert(RandomNumberGenerator::seeded(
+        save.options.monster_seed(&save.depth),
+    ));
+
+    *options = GameOptions {
+        mode: GameMode::Play,
+        ..save.options
+    };
+
+    Ok(())
+}
+
+/// Checks the version before reading the rest, a save of another version is turned down
+/// rather than half read
+fn parse_save(contents: &str) -> Result<SaveGame, SaveError> {
+    let header: SaveHeader = serde_json::from_str(contents)?;
+    if header.version != SAVE_VERSION {
+        return Err(SaveError::Version(header.version));
+    }
+
+    Ok(serde_json::from_str(contents)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let contents = format!("{{ \"version\": {}, \"depth\": 1 }}", SAVE_VERSION + 1);
+        match parse_save(&contents) {
+            Err(SaveError::Version(found)) => assert_eq!(found, SAVE_VERSION + 1),
+            Err(e) => panic!("expected a version error, got {}", e),
+            Ok(_) => panic!("expected version {} to be rejected", SAVE_VERSION + 1),
+        }
+    }
+
+    #[test]
+    fn corrupt_saves_are_rejected() {
+        let contents = format!("{{ \"version\": {}, \"depth\": 1 }}", SAVE_VERSION);
+        assert!(matches!(parse_save(&contents), Err(SaveError::Format(_))));
+        assert!(matches!(
+            parse_save("not a save"),
+            Err(SaveError::Format(_))
+        ));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::prelude::*;
+
+const NUM_TILES: usize = (SCREEN_HEIGHT * SCREEN_WIDTH) as usize;
+
+/// A resource listing the entities on every tile of the map, so finding what sits at a
+/// point does not need a scan of every positioned entity. It is rebuilt at the start of
+/// every schedule and kept up to date by the systems moving or removing entities in between
+#[derive(Clone, Debug)]
+pub struct SpatialIndex {
+    contents: Vec<Vec<Entity>>,
+    // how many blocking entities stand on each tile
+    blockers: Vec<usize>,
+}
+
+impl Default for SpatialIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpatialIndex {
+    pub fn new() -> Self {
+        Self {
+            contents: vec![Vec::new(); NUM_TILES],
+            blockers: vec![0; NUM_TILES],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.contents
+            .iter_mut()
+            .for_each(|entities| entities.clear());
+        self.blockers.iter_mut().for_each(|count| *count = 0);
+    }
+
+    fn try_idx(pos: Point) -> Option<usize> {
+        if pos.x >= 0 && pos.x < SCREEN_WIDTH && pos.y >= 0 && pos.y < SCREEN_HEIGHT {
+            Some(map_idx(pos.x, pos.y))
+        } else {
+            None
+        }
+    }
+
+    /// Records an entity standing at `pos`, blocking entities keep others off the tile
+    pub fn add(&mut self, entity: Entity, pos: Point, blocks: bool) {
+        if let Some(idx) = Self::try_idx(pos) {
+            self.contents[idx].push(entity);
+            if blocks {
+                self.blockers[idx] += 1;
+            }
+        }
+    }
+
+    /// Forgets an entity at `pos`, the tile stays blocked while another blocker remains
+    pub fn remove(&mut self, entity: Entity, pos: Point, blocks: bool) {
+        if let Some(idx) = Self::try_idx(pos) {
+            let before = self.contents[idx].len();
+            self.contents[idx].retain(|e| *e != entity);
+            if blocks && self.contents[idx].len() < before {
+                self.blockers[idx] -= 1;
+            }
+        }
+    }
+
+    pub fn move_entity(&mut self, entity: Entity, from: Point, to: Point, blocks: bool) {
+        self.remove(entity, from, blocks);
+        self.add(entity, to, blocks);
+    }
+
+    pub fn entities_at(&self, pos: Point) -> &[Entity] {
+        match Self::try_idx(pos) {
+            Some(idx) => &self.contents[idx],
+            None => &[],
+        }
+    }
+
+    pub fn is_blocked(&self, pos: Point) -> bool {
+        Self::try_idx(pos).is_some_and(|idx| self.blockers[idx] > 0)
+    }
+}
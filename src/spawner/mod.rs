@@ -0,0 +1,295 @@
+mod template;
+
+use crate::prelude::*;
+use legion::world::Entry;
+
+pub use template::*;
+
+/// The mana the player starts out with, levelling up adds to it
+pub const PLAYER_MANA: i32 = 10;
+
+/// Push a 'player' entity onto the world, represented as a tuple of components
+pub fn spawn_player(ecs: &mut World, camera: &mut Camera, position: Point, fov: i32) {
+    camera.on_player_move(position);
+    let player = ecs.push((
+        Player,
+        position,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: to_cp437('@'),
+        },
+        Health {
+            current: 20,
+            max: 20,
+        },
+        Name("Player".to_string()),
+        FieldOfView::new(fov),
+        Attack::new(3, "1d6"),
+        Defense { armour: 1 },
+    ));
+
+    // the player gets the first move of the game
+    if let Some(mut entry) = ecs.entry(player) {
+        entry.add_component(Energy {
+            current: ACTION_COST,
+            speed: NORMAL_SPEED,
+        });
+        entry.add_component(BlocksTile);
+        entry.add_component(Faction::Player);
+        entry.add_component(Experience::new());
+        entry.add_component(Mana::new(PLAYER_MANA));
+    }
+}
+
+/// Fill a spawn location with whatever the spawn table comes up with
+pub fn spawn_entity(
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    table: &SpawnTable,
+    pos: Point,
+    fov: i32,
+) {
+    if let Some(template) = table.roll(rng) {
+        spawn_template(ecs, rng, template, pos, fov);
+    }
+}
+
+/// Push whatever the template describes, a monster, an item or a trap
+pub fn spawn_template(
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    template: &Template,
+    pos: Point,
+    fov: i32,
+) {
+    match &template.kind {
+        TemplateKind::Monster { .. } => spawn_monster(ecs, rng, template, pos, fov),
+        TemplateKind::Item { .. } => spawn_item(ecs, template, pos),
+        TemplateKind::Trap { .. } => spawn_trap(ecs, template, pos),
+    }
+}
+
+/// Push a 'monster' entity onto the world, represented as a tuple of different components
+pub fn spawn_monster(
+    ecs: &mut World,
+    rng: &mut RandomNumberGenerator,
+    template: &Template,
+    pos: Point,
+    fov: i32,
+) {
+    let (hp, attack, defense, speed, faction, ai, xp, inflicts, ranged, locomotion) =
+        match &template.kind {
+            TemplateKind::Monster {
+                hp,
+                attack,
+                defense,
+                speed,
+                faction,
+                ai,
+                xp,
+                inflicts,
+                ranged,
+                locomotion,
+                ..
+            } => (
+                *hp,
+                *attack,
+                *defense,
+                *speed,
+                *faction,
+                *ai,
+                *xp,
+                *inflicts,
+                *ranged,
+                *locomotion,
+            ),
+            _ => return,
+        };
+
+    let entity = ecs.push((
+        Enemy,
+        pos,
+        Render {
+            color: ColorPair::new(template.color, BLACK),
+            glyph: template.glyph,
+        },
+        Health {
+            current: hp,
+            max: hp,
+        },
+        Name(template.name.clone()),
+        FieldOfView::new(fov),
+        attack,
+        defense,
+    ));
+
+    // legion only accepts up to 8 components in one push
+    if let Some(mut entry) = ecs.entry(entity) {
+        entry.add_component(Energy::new(speed));
+        entry.add_component(BlocksTile);
+        entry.add_component(faction);
+        entry.add_component(ExperienceValue { xp });
+        if let Some(inflicts) = inflicts {
+            entry.add_component(inflicts);
+        }
+        if let Some(range) = ranged {
+            entry.add_component(Ranged { range });
+        }
+        if let Some(locomotion) = locomotion {
+            entry.add_component(locomotion);
+        }
+
+        match ai {
+            AiKind::Chasing => {
+                entry.add_component(ChasingPlayer);
+                // some monsters sleep until the player shows up, the others roam about
+                entry.add_component(if rng.range(0, 3) == 0 {
+                    AiState::Idle
+                } else {
+                    AiState::Wandering
+                });
+            }
+            AiKind::Random => entry.add_component(MovesRandomly),
+        }
+    }
+
+    if let TemplateKind::Monster {
+        abilities, mana, ..
+    } = &template.kind
+    {
+        if !abilities.is_empty() {
+            if let Some(mut entry) = ecs.entry(entity) {
+                entry.add_component(Mana::new(*mana));
+            }
+            abilities
+                .iter()
+                .for_each(|ability| learn_ability(ecs, entity, ability));
+        }
+    }
+}
+
+/// Push an ability known by `owner`, it is ready to be cast straight away
+pub fn learn_ability(ecs: &mut World, owner: Entity, ability: &AbilityTemplate) {
+    let entity = ecs.push((
+        Ability {
+            owner,
+            mana: ability.mana,
+            cooldown: ability.cooldown,
+            ready_in: 0,
+        },
+        Name(ability.name.clone()),
+    ));
+
+    if let Some(mut entry) = ecs.entry(entity) {
+        add_effects(&mut entry, &ability.effects, ability.ranged, ability.area);
+    }
+}
+
+/// Push an item lying on the floor, its effects and equipment bonuses become components
+pub fn spawn_item(ecs: &mut World, template: &Template, pos: Point) {
+    let (consumable, effects, equipment, ranged, area) = match &template.kind {
+        TemplateKind::Item {
+            consumable,
+            effects,
+            equipment,
+            ranged,
+            area,
+        } => (*consumable, effects, *equipment, *ranged, *area),
+        _ => return,
+    };
+
+    let entity = ecs.push((
+        Item,
+        pos,
+        Render {
+            color: ColorPair::new(template.color, BLACK),
+            glyph: template.glyph,
+        },
+        Name(template.name.clone()),
+    ));
+
+    if let Some(mut entry) = ecs.entry(entity) {
+        if consumable {
+            entry.add_component(Consumable);
+        }
+
+        add_effects(&mut entry, effects, ranged, area);
+
+        if let Some(equipment) = equipment {
+            entry.add_component(Equippable {
+                slot: equipment.slot,
+            });
+            if equipment.to_hit != 0 || equipment.damage != 0 {
+                entry.add_component(AttackBonus {
+                    to_hit: equipment.to_hit,
+                    damage: equipment.damage,
+                });
+            }
+            if equipment.armour != 0 {
+                entry.add_component(DefenseBonus {
+                    armour: equipment.armour,
+                });
+            }
+        }
+    }
+}
+
+/// Push a trap, it goes off on the first one to step on it
+pub fn spawn_trap(ecs: &mut World, template: &Template, pos: Point) {
+    let effect = match &template.kind {
+        TemplateKind::Trap { effect } => *effect,
+        _ => return,
+    };
+
+    ecs.push((
+        Trap,
+        pos,
+        Render {
+            color: ColorPair::new(template.color, BLACK),
+            glyph: template.glyph,
+        },
+        Name(template.name.clone()),
+        InflictsStatus {
+            effect,
+            chance: 100,
+        },
+    ));
+}
+
+pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
+    ecs.push((
+        Item,
+        AmuletOfYala,
+        pos,
+        Render {
+            color: ColorPair::new(WHITE, BLACK),
+            glyph: to_cp437('|'),
+        },
+        Name("Amulet of Yala".to_string()),
+    ));
+}
+
+/// Items and abilities do their work through the same components, one per effect
+fn add_effects(entry: &mut Entry, effects: &[ItemEffect], ranged: Option<i32>, area: Option<i32>) {
+    effects.iter().for_each(|effect| match *effect {
+        ItemEffect::Healing(amount) => entry.add_component(ProvidesHealing { amount }),
+        ItemEffect::DungeonMap => entry.add_component(ProvidesDungeonMap),
+        ItemEffect::Teleport => entry.add_component(ProvidesTeleport),
+        ItemEffect::Vision { bonus, turns } => entry.add_component(ProvidesVision { bonus, turns }),
+        ItemEffect::Haste(turns) => entry.add_component(ProvidesHaste { turns }),
+        ItemEffect::Slow(turns) => entry.add_component(ProvidesSlow { turns }),
+        ItemEffect::Charm(turns) => entry.add_component(ProvidesCharm { turns }),
+        ItemEffect::Inflicts(effect) => entry.add_component(InflictsStatus {
+            effect,
+            chance: 100,
+        }),
+        ItemEffect::Damage(damage) => entry.add_component(InflictsDamage { damage }),
+        ItemEffect::Blink => entry.add_component(ProvidesBlink),
+    });
+    if let Some(range) = ranged {
+        entry.add_component(Ranged { range });
+    }
+    if let Some(radius) = area {
+        entry.add_component(AreaOfEffect { radius });
+    }
+}
@@ -0,0 +1,1028 @@
+use crate::prelude::*;
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+
+/// Every monster and item that can turn up in the dungeon is described here
+pub const TEMPLATE_FILE: &str = "resources/templates.json";
+
+#[derive(Debug)]
+pub enum TemplateError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    /// a single template is wrong, `entry` names it
+    Entry {
+        entry: String,
+        problem: String,
+    },
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Io(e) => write!(f, "could not read {}: {}", TEMPLATE_FILE, e),
+            TemplateError::Format(e) => write!(f, "{} is not valid: {}", TEMPLATE_FILE, e),
+            TemplateError::Entry { entry, problem } => {
+                write!(f, "{}: template '{}' {}", TEMPLATE_FILE, entry, problem)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl From<std::io::Error> for TemplateError {
+    fn from(e: std::io::Error) -> Self {
+        TemplateError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for TemplateError {
+    fn from(e: serde_json::Error) -> Self {
+        TemplateError::Format(e)
+    }
+}
+
+/// How a monster decides where to go
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiKind {
+    /// runs the AI state machine, hunting down whatever it is hostile to
+    Chasing,
+    /// stumbles about at random
+    Random,
+}
+
+/// A component an item or ability brings along, e.g. `{ "healing": 6 }` or `"teleport"`
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemEffect {
+    Healing(i32),
+    DungeonMap,
+    Teleport,
+    Vision {
+        bonus: i32,
+        turns: i32,
+    },
+    Haste(i32),
+    Slow(i32),
+    Charm(i32),
+    /// befalls every enemy in view, or whoever is caught by an aimed item,
+    /// e.g. `{ "inflicts": { "sleep": 8 } }`
+    Inflicts(StatusEffect),
+    /// hurts whoever is caught by an aimed item, so it needs a `ranged`
+    Damage(i32),
+    /// moves the user to the tile aimed at, so it needs a `ranged`
+    Blink,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+pub struct EquipmentTemplate {
+    pub slot: EquipmentSlot,
+    #[serde(default)]
+    pub to_hit: i32,
+    #[serde(default)]
+    pub damage: i32,
+    #[serde(default)]
+    pub armour: i32,
+}
+
+/// A monster as written in the template file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MonsterEntry {
+    name: String,
+    glyph: String,
+    color: String,
+    hp: i32,
+    #[serde(default)]
+    to_hit: i32,
+    damage: String,
+    #[serde(default)]
+    armour: i32,
+    speed: i32,
+    faction: Faction,
+    ai: AiKind,
+    xp: i32,
+    inflicts: Option<InflictsStatus>,
+    /// shoots at whatever it hunts from this many tiles away
+    ranged: Option<i32>,
+    /// names of entries in the abilities section
+    #[serde(default)]
+    abilities: Vec<String>,
+    #[serde(default)]
+    mana: i32,
+    /// "flying" or "swimming", monsters walk otherwise
+    locomotion: Option<Locomotion>,
+    frequency: i32,
+    #[serde(default)]
+    themes: BTreeMap<ThemeKind, i32>,
+    #[serde(default)]
+    depth_bonus: i32,
+    min_depth: i32,
+    max_depth: Option<i32>,
+    #[serde(default)]
+    guaranteed: usize,
+    #[serde(default)]
+    tables: BTreeMap<String, i32>,
+}
+
+/// An item as written in the template file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ItemEntry {
+    name: String,
+    glyph: String,
+    color: String,
+    frequency: i32,
+    #[serde(default)]
+    themes: BTreeMap<ThemeKind, i32>,
+    #[serde(default)]
+    depth_bonus: i32,
+    min_depth: i32,
+    max_depth: Option<i32>,
+    #[serde(default)]
+    guaranteed: usize,
+    #[serde(default)]
+    tables: BTreeMap<String, i32>,
+    #[serde(default)]
+    consumable: bool,
+    #[serde(default)]
+    effects: Vec<ItemEffect>,
+    equipment: Option<EquipmentTemplate>,
+    /// thrown or aimed at a tile this many tiles away, or shot when it is a weapon
+    ranged: Option<i32>,
+    /// the radius of the blast around the tile aimed at
+    area: Option<i32>,
+}
+
+/// An ability as written in the template file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AbilityEntry {
+    name: String,
+    mana: i32,
+    cooldown: i32,
+    ranged: Option<i32>,
+    area: Option<i32>,
+    effects: Vec<ItemEffect>,
+    /// the player knows it from the start
+    #[serde(default)]
+    starting: bool,
+}
+
+/// A trap as written in the template file
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TrapEntry {
+    name: String,
+    glyph: String,
+    color: String,
+    frequency: i32,
+    #[serde(default)]
+    themes: BTreeMap<ThemeKind, i32>,
+    #[serde(default)]
+    depth_bonus: i32,
+    min_depth: i32,
+    max_depth: Option<i32>,
+    #[serde(default)]
+    guaranteed: usize,
+    #[serde(default)]
+    tables: BTreeMap<String, i32>,
+    effect: StatusEffect,
+}
+
+#[derive(Deserialize)]
+struct TemplateFile {
+    monsters: Vec<serde_json::Value>,
+    items: Vec<serde_json::Value>,
+    #[serde(default)]
+    traps: Vec<serde_json::Value>,
+    #[serde(default)]
+    abilities: Vec<serde_json::Value>,
+}
+
+#[derive(Clone, Debug)]
+pub enum TemplateKind {
+    Monster {
+        hp: i32,
+        attack: Attack,
+        defense: Defense,
+        speed: i32,
+        faction: Faction,
+        ai: AiKind,
+        /// awarded to whoever kills it
+        xp: i32,
+        /// a chance to poison, confuse... whatever it hits
+        inflicts: Option<InflictsStatus>,
+        ranged: Option<i32>,
+        abilities: Vec<AbilityTemplate>,
+        mana: i32,
+        locomotion: Option<Locomotion>,
+    },
+    Item {
+        consumable: bool,
+        effects: Vec<ItemEffect>,
+        equipment: Option<EquipmentTemplate>,
+        ranged: Option<i32>,
+        area: Option<i32>,
+    },
+    Trap {
+        effect: StatusEffect,
+    },
+}
+
+/// Where and how often a template turns up
+#[derive(Clone, Debug)]
+pub struct SpawnRules {
+    /// relative chance of being picked, compared to the other templates allowed on a depth
+    pub frequency: i32,
+    /// replaces `frequency` on levels with one of these themes
+    pub themes: BTreeMap<ThemeKind, i32>,
+    /// added to the frequency for every depth below `min_depth`, negative makes it rarer
+    pub depth_bonus: i32,
+    /// the shallowest depth this can be found on
+    pub min_depth: i32,
+    /// the deepest depth this can be found on
+    pub max_depth: i32,
+    /// how many of these every level holds, on depths and themes where it can appear at all
+    pub guaranteed: usize,
+    /// frequencies in the named tables map builders ask for, e.g. "vault"
+    pub tables: BTreeMap<String, i32>,
+}
+
+impl SpawnRules {
+    pub fn allowed_on(&self, depth: &Depth) -> bool {
+        (self.min_depth..=self.max_depth).contains(&depth.0)
+    }
+
+    /// The chance of turning up on an ordinary spawn point of a level
+    pub fn level_weight(&self, depth: &Depth, theme: ThemeKind) -> i32 {
+        let base = self.themes.get(&theme).copied().unwrap_or(self.frequency);
+        self.weight(base, depth)
+    }
+
+    /// The chance of being picked from the table called `table`
+    pub fn table_weight(&self, table: &str, depth: &Depth) -> i32 {
+        let base = self.tables.get(table).copied().unwrap_or(0);
+        self.weight(base, depth)
+    }
+
+    fn weight(&self, base: i32, depth: &Depth) -> i32 {
+        // the depth bonus only shifts the odds, it never makes something appear out of nothing
+        if base < 1 || !self.allowed_on(depth) {
+            return 0;
+        }
+        (base + self.depth_bonus * (depth.0 - self.min_depth)).max(0)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.frequency < 0 {
+            return Err(format!(
+                "has frequency {}, expected 0 or more",
+                self.frequency
+            ));
+        }
+        if let Some((theme, frequency)) = self.themes.iter().find(|(_, f)| **f < 0) {
+            return Err(format!(
+                "has frequency {} for theme {:?}, expected 0 or more",
+                frequency, theme
+            ));
+        }
+        if let Some((table, frequency)) = self.tables.iter().find(|(_, f)| **f < 1) {
+            return Err(format!(
+                "has frequency {} in table '{}', expected at least 1",
+                frequency, table
+            ));
+        }
+        if !(1..=FINAL_DEPTH).contains(&self.min_depth) {
+            return Err(format!(
+                "has min_depth {}, expected 1 to {}",
+                self.min_depth, FINAL_DEPTH
+            ));
+        }
+        if !(self.min_depth..=FINAL_DEPTH).contains(&self.max_depth) {
+            return Err(format!(
+                "has max_depth {}, expected {} to {}",
+                self.max_depth, self.min_depth, FINAL_DEPTH
+            ));
+        }
+
+        let on_levels = self.frequency > 0 || self.themes.values().any(|f| *f > 0);
+        if !on_levels && self.tables.is_empty() {
+            return Err("can never spawn, it needs a frequency, a theme or a table".to_string());
+        }
+        if !on_levels && self.guaranteed > 0 {
+            return Err("is guaranteed but never spawns on a level".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A validated ability, learnt by monsters when they spawn and by the player at the start
+#[derive(Clone, Debug)]
+pub struct AbilityTemplate {
+    pub name: String,
+    pub mana: i32,
+    pub cooldown: i32,
+    pub ranged: Option<i32>,
+    pub area: Option<i32>,
+    pub effects: Vec<ItemEffect>,
+    pub starting: bool,
+}
+
+/// A validated template, ready to be spawned
+#[derive(Clone, Debug)]
+pub struct Template {
+    pub name: String,
+    pub glyph: FontCharType,
+    pub color: RGB,
+    pub spawn: SpawnRules,
+    pub kind: TemplateKind,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Templates {
+    pub templates: Vec<Template>,
+    pub abilities: Vec<AbilityTemplate>,
+}
+
+impl Templates {
+    pub fn load() -> Result<Self, TemplateError> {
+        Self::parse(&fs::read_to_string(TEMPLATE_FILE)?)
+    }
+
+    /// Checks every entry, the first problem found is reported with the entry it belongs to
+    pub fn parse(json: &str) -> Result<Self, TemplateError> {
+        let file: TemplateFile = serde_json::from_str(json)?;
+        let mut templates = Vec::new();
+        let mut abilities = Vec::new();
+
+        // monsters refer to abilities, so those come first
+        for (n, value) in file.abilities.into_iter().enumerate() {
+            let entry = entry_name(&value, "ability", n);
+            let ability: AbilityEntry = from_value(value, &entry)?;
+            abilities.push(resolve_ability(ability).map_err(|problem| invalid(&entry, problem))?);
+        }
+
+        for (n, value) in file.monsters.into_iter().enumerate() {
+            let entry = entry_name(&value, "monster", n);
+            let monster: MonsterEntry = from_value(value, &entry)?;
+            templates.push(
+                resolve_monster(monster, &abilities).map_err(|problem| invalid(&entry, problem))?,
+            );
+        }
+
+        for (n, value) in file.items.into_iter().enumerate() {
+            let entry = entry_name(&value, "item", n);
+            let item: ItemEntry = from_value(value, &entry)?;
+            templates.push(resolve_item(item).map_err(|problem| invalid(&entry, problem))?);
+        }
+
+        for (n, value) in file.traps.into_iter().enumerate() {
+            let entry = entry_name(&value, "trap", n);
+            let trap: TrapEntry = from_value(value, &entry)?;
+            templates.push(resolve_trap(trap).map_err(|problem| invalid(&entry, problem))?);
+        }
+
+        let mut names = HashSet::new();
+        let all_names = templates
+            .iter()
+            .map(|t| t.name.as_str())
+            .chain(abilities.iter().map(|a| a.name.as_str()));
+        for name in all_names {
+            if !names.insert(name) {
+                return Err(invalid(name, "is defined more than once".to_string()));
+            }
+        }
+
+        Ok(Self {
+            templates,
+            abilities,
+        })
+    }
+}
+
+/// Entries are named after their `name`, or their position when that is missing
+fn entry_name(value: &serde_json::Value, kind: &str, n: usize) -> String {
+    value
+        .get("name")
+        .and_then(|name| name.as_str())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| format!("{} #{}", kind, n + 1))
+}
+
+fn from_value<T: serde::de::DeserializeOwned>(
+    value: serde_json::Value,
+    entry: &str,
+) -> Result<T, TemplateError> {
+    serde_json::from_value(value).map_err(|e| invalid(entry, format!("is malformed: {}", e)))
+}
+
+fn invalid(entry: &str, problem: String) -> TemplateError {
+    TemplateError::Entry {
+        entry: entry.to_string(),
+        problem,
+    }
+}
+
+fn resolve_glyph(glyph: &str) -> Result<FontCharType, String> {
+    let mut chars = glyph.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if to_cp437(c) != 0 => Ok(to_cp437(c)),
+        _ => Err(format!(
+            "has glyph '{}', expected a single printable character",
+            glyph
+        )),
+    }
+}
+
+fn resolve_color(color: &str) -> Result<RGB, String> {
+    RGB::from_hex(color).map_err(|_| format!("has colour '{}', expected e.g. \"#FF8800\"", color))
+}
+
+fn resolve_monster(
+    monster: MonsterEntry,
+    abilities: &[AbilityTemplate],
+) -> Result<Template, String> {
+    if monster.hp < 1 {
+        return Err(format!("has hp {}, expected at least 1", monster.hp));
+    }
+    if monster.speed < 1 {
+        return Err(format!("has speed {}, expected at least 1", monster.speed));
+    }
+    if monster.xp < 0 {
+        return Err(format!("has xp {}, expected 0 or more", monster.xp));
+    }
+    if let Some(inflicts) = monster.inflicts {
+        if !(1..=100).contains(&inflicts.chance) {
+            return Err(format!(
+                "has a {}% chance to inflict, expected 1 to 100",
+                inflicts.chance
+            ));
+        }
+        check_effect(&inflicts.effect)?;
+    }
+    if let Some(range) = monster.ranged {
+        if range < 2 {
+            return Err(format!("has range {}, expected at least 2", range));
+        }
+    }
+    if monster.mana < 0 {
+        return Err(format!("has mana {}, expected 0 or more", monster.mana));
+    }
+    let known = monster
+        .abilities
+        .iter()
+        .map(|name| {
+            abilities
+                .iter()
+                .find(|ability| ability.name == *name)
+                .cloned()
+                .ok_or_else(|| format!("knows the ability '{}', which is not defined", name))
+        })
+        .collect::<Result<Vec<AbilityTemplate>, String>>()?;
+    if let Some(ability) = known.iter().find(|ability| ability.mana > monster.mana) {
+        return Err(format!(
+            "knows the ability '{}' but has too little mana to cast it",
+            ability.name
+        ));
+    }
+    let damage = parse_dice_string(&monster.damage).map_err(|_| {
+        format!(
+            "has damage '{}', expected dice like \"1d6+1\"",
+            monster.damage
+        )
+    })?;
+
+    let spawn = SpawnRules {
+        frequency: monster.frequency,
+        themes: monster.themes,
+        depth_bonus: monster.depth_bonus,
+        min_depth: monster.min_depth,
+        max_depth: monster.max_depth.unwrap_or(FINAL_DEPTH),
+        guaranteed: monster.guaranteed,
+        tables: monster.tables,
+    };
+    spawn.check()?;
+
+    Ok(Template {
+        glyph: resolve_glyph(&monster.glyph)?,
+        color: resolve_color(&monster.color)?,
+        name: monster.name,
+        spawn,
+        kind: TemplateKind::Monster {
+            hp: monster.hp,
+            attack: Attack {
+                to_hit: monster.to_hit,
+                damage,
+            },
+            defense: Defense {
+                armour: monster.armour,
+            },
+            speed: monster.speed,
+            faction: monster.faction,
+            ai: monster.ai,
+            xp: monster.xp,
+            inflicts: monster.inflicts,
+            ranged: monster.ranged,
+            abilities: known,
+            mana: monster.mana,
+            locomotion: monster.locomotion,
+        },
+    })
+}
+
+fn resolve_item(item: ItemEntry) -> Result<Template, String> {
+    let spawn = SpawnRules {
+        frequency: item.frequency,
+        themes: item.themes,
+        depth_bonus: item.depth_bonus,
+        min_depth: item.min_depth,
+        max_depth: item.max_depth.unwrap_or(FINAL_DEPTH),
+        guaranteed: item.guaranteed,
+        tables: item.tables,
+    };
+    spawn.check()?;
+    if item.effects.is_empty() && item.equipment.is_none() {
+        return Err("has neither effects nor equipment, it would do nothing".to_string());
+    }
+    if item.consumable && item.equipment.is_some() {
+        return Err("is both consumable and equipment".to_string());
+    }
+    if item.ranged.is_some()
+        && item
+            .equipment
+            .is_some_and(|equipment| equipment.slot != EquipmentSlot::Weapon)
+    {
+        return Err("has a range but is not a weapon".to_string());
+    }
+    if item.area.is_some() && item.equipment.is_some() {
+        return Err("has an area but is not thrown or aimed".to_string());
+    }
+    check_effects(&item.effects, item.ranged, item.area)?;
+
+    Ok(Template {
+        glyph: resolve_glyph(&item.glyph)?,
+        color: resolve_color(&item.color)?,
+        name: item.name,
+        spawn,
+        kind: TemplateKind::Item {
+            consumable: item.consumable,
+            effects: item.effects,
+            equipment: item.equipment,
+            ranged: item.ranged,
+            area: item.area,
+        },
+    })
+}
+
+fn resolve_trap(trap: TrapEntry) -> Result<Template, String> {
+    let spawn = SpawnRules {
+        frequency: trap.frequency,
+        themes: trap.themes,
+        depth_bonus: trap.depth_bonus,
+        min_depth: trap.min_depth,
+        max_depth: trap.max_depth.unwrap_or(FINAL_DEPTH),
+        guaranteed: trap.guaranteed,
+        tables: trap.tables,
+    };
+    spawn.check()?;
+    check_effect(&trap.effect)?;
+
+    Ok(Template {
+        glyph: resolve_glyph(&trap.glyph)?,
+        color: resolve_color(&trap.color)?,
+        name: trap.name,
+        spawn,
+        kind: TemplateKind::Trap {
+            effect: trap.effect,
+        },
+    })
+}
+
+fn resolve_ability(ability: AbilityEntry) -> Result<AbilityTemplate, String> {
+    if ability.mana < 0 {
+        return Err(format!("costs {} mana, expected 0 or more", ability.mana));
+    }
+    if ability.cooldown < 0 {
+        return Err(format!(
+            "has a cooldown of {}, expected 0 or more",
+            ability.cooldown
+        ));
+    }
+    if ability.effects.is_empty() {
+        return Err("has no effects, it would do nothing".to_string());
+    }
+    check_effects(&ability.effects, ability.ranged, ability.area)?;
+
+    Ok(AbilityTemplate {
+        name: ability.name,
+        mana: ability.mana,
+        cooldown: ability.cooldown,
+        ranged: ability.ranged,
+        area: ability.area,
+        effects: ability.effects,
+        starting: ability.starting,
+    })
+}
+
+/// The checks items and abilities share, aimed effects need a range to aim with
+fn check_effects(
+    effects: &[ItemEffect],
+    ranged: Option<i32>,
+    area: Option<i32>,
+) -> Result<(), String> {
+    for effect in effects.iter() {
+        match *effect {
+            ItemEffect::Inflicts(ref effect) => check_effect(effect)?,
+            ItemEffect::Damage(damage) if damage < 1 => {
+                return Err(format!("does {} damage, expected at least 1", damage))
+            }
+            ItemEffect::Damage(_) if ranged.is_none() => {
+                return Err("does damage but has no range to throw it".to_string())
+            }
+            ItemEffect::Blink if ranged.is_none() => {
+                return Err("blinks but has no range to aim it".to_string())
+            }
+            _ => {}
+        }
+    }
+    if let Some(range) = ranged {
+        if range < 1 {
+            return Err(format!("has range {}, expected at least 1", range));
+        }
+    }
+    if let Some(area) = area {
+        if area < 1 {
+            return Err(format!("has area {}, expected at least 1", area));
+        }
+        if ranged.is_none() {
+            return Err("has an area but is not thrown or aimed".to_string());
+        }
+    }
+    Ok(())
+}
+
+fn check_effect(effect: &StatusEffect) -> Result<(), String> {
+    let (turns, damage) = match *effect {
+        StatusEffect::Poison { turns, damage } => (turns, damage),
+        StatusEffect::Confusion(turns)
+        | StatusEffect::Sleep(turns)
+        | StatusEffect::Haste(turns)
+        | StatusEffect::Slow(turns)
+        | StatusEffect::Fear(turns) => (turns, 1),
+    };
+    if turns < 1 {
+        return Err(format!(
+            "has an effect lasting {} turns, expected at least 1",
+            turns
+        ));
+    }
+    if damage < 1 {
+        return Err(format!(
+            "has poison doing {} damage, expected at least 1",
+            damage
+        ));
+    }
+    Ok(())
+}
+
+/// The templates that may appear in one place, each weighted by its chance of being picked
+pub struct SpawnTable<'a> {
+    entries: Vec<(&'a Template, i32)>,
+    total: i32,
+}
+
+impl<'a> SpawnTable<'a> {
+    /// What turns up on the spawn points of a level with the given depth and theme
+    pub fn for_level(templates: &'a Templates, depth: &Depth, theme: ThemeKind) -> Self {
+        Self::weighted(templates, |spawn| spawn.level_weight(depth, theme))
+    }
+
+    /// One of the named tables map builders can ask for, see `MapBuilder::spawn_in_region`
+    pub fn named(templates: &'a Templates, table: &str, depth: &Depth) -> Self {
+        Self::weighted(templates, |spawn| spawn.table_weight(table, depth))
+    }
+
+    fn weighted<F>(templates: &'a Templates, weight: F) -> Self
+    where
+        F: Fn(&SpawnRules) -> i32,
+    {
+        let entries: Vec<(&Template, i32)> = templates
+            .templates
+            .iter()
+            .map(|template| (template, weight(&template.spawn)))
+            .filter(|(_, weight)| *weight > 0)
+            .collect();
+        let total = entries.iter().map(|(_, weight)| weight).sum();
+
+        Self { entries, total }
+    }
+
+    /// Every template that has to be placed before rolling, once per guaranteed copy
+    pub fn guaranteed(&self) -> impl Iterator<Item = &'a Template> + '_ {
+        self.entries
+            .iter()
+            .flat_map(|(template, _)| std::iter::repeat_n(*template, template.spawn.guaranteed))
+    }
+
+    pub fn roll(&self, rng: &mut RandomNumberGenerator) -> Option<&'a Template> {
+        if self.total < 1 {
+            return None;
+        }
+
+        let mut roll = rng.range(0, self.total);
+        for (template, weight) in self.entries.iter() {
+            if roll < *weight {
+                return Some(template);
+            }
+            roll -= weight;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn goblin() -> Value {
+        json!({
+            "name": "Goblin",
+            "glyph": "g",
+            "color": "#FFFFFF",
+            "hp": 4,
+            "damage": "1d3",
+            "speed": 100,
+            "faction": "Goblins",
+            "ai": "chasing",
+            "xp": 5,
+            "frequency": 100,
+            "min_depth": 1
+        })
+    }
+
+    fn potion() -> Value {
+        json!({
+            "name": "Healing Potion",
+            "glyph": "!",
+            "color": "#FF0000",
+            "frequency": 20,
+            "min_depth": 1,
+            "consumable": true,
+            "effects": [{ "healing": 6 }]
+        })
+    }
+
+    fn needle() -> Value {
+        json!({
+            "name": "Poison Needle",
+            "glyph": "^",
+            "color": "#00FF00",
+            "frequency": 6,
+            "min_depth": 1,
+            "effect": { "poison": { "turns": 4, "damage": 1 } }
+        })
+    }
+
+    fn missile() -> Value {
+        json!({
+            "name": "Magic Missile",
+            "mana": 3,
+            "cooldown": 2,
+            "ranged": 6,
+            "effects": [{ "damage": 4 }]
+        })
+    }
+
+    fn file(
+        monsters: Vec<Value>,
+        items: Vec<Value>,
+        traps: Vec<Value>,
+        abilities: Vec<Value>,
+    ) -> String {
+        json!({
+            "monsters": monsters,
+            "items": items,
+            "traps": traps,
+            "abilities": abilities
+        })
+        .to_string()
+    }
+
+    fn with(mut entry: Value, field: &str, value: Value) -> Value {
+        entry[field] = value;
+        entry
+    }
+
+    /// The file is turned down, naming `entry` and what is wrong with it
+    fn assert_rejected(json: String, entry: &str, problem: &str) {
+        match Templates::parse(&json) {
+            Err(e @ TemplateError::Entry { .. }) => {
+                let message = e.to_string();
+                assert!(
+                    message.contains(&format!("'{}'", entry)),
+                    "{} does not name {}",
+                    message,
+                    entry
+                );
+                assert!(
+                    message.contains(problem),
+                    "{} is not about {}",
+                    message,
+                    problem
+                );
+            }
+            Err(e) => panic!("expected a problem with {}, got {}", entry, e),
+            Ok(_) => panic!("expected {} to be rejected", entry),
+        }
+    }
+
+    #[test]
+    fn the_shipped_templates_load() {
+        Templates::load().unwrap();
+    }
+
+    #[test]
+    fn valid_entries_parse() {
+        let templates = Templates::parse(&file(
+            vec![goblin()],
+            vec![potion()],
+            vec![needle()],
+            vec![missile()],
+        ))
+        .unwrap();
+        assert_eq!(templates.templates.len(), 3);
+        assert_eq!(templates.abilities.len(), 1);
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let json = file(
+            vec![with(goblin(), "wings", json!(true))],
+            vec![],
+            vec![],
+            vec![],
+        );
+        assert_rejected(json, "Goblin", "unknown field `wings`");
+    }
+
+    #[test]
+    fn unknown_effects_are_rejected() {
+        let potion = with(potion(), "effects", json!([{ "levitation": 3 }]));
+        assert_rejected(
+            file(vec![], vec![potion], vec![], vec![]),
+            "Healing Potion",
+            "is malformed",
+        );
+    }
+
+    #[test]
+    fn unnamed_entries_are_named_by_position() {
+        let mut nameless = goblin();
+        nameless.as_object_mut().unwrap().remove("name");
+        let json = file(vec![goblin(), nameless], vec![], vec![], vec![]);
+        assert_rejected(json, "monster #2", "missing field `name`");
+    }
+
+    #[test]
+    fn bad_colours_are_rejected() {
+        let json = file(
+            vec![],
+            vec![],
+            vec![with(needle(), "color", json!("green"))],
+            vec![],
+        );
+        assert_rejected(json, "Poison Needle", "has colour 'green'");
+    }
+
+    #[test]
+    fn bad_glyphs_are_rejected() {
+        let json = file(
+            vec![with(goblin(), "glyph", json!("gg"))],
+            vec![],
+            vec![],
+            vec![],
+        );
+        assert_rejected(json, "Goblin", "has glyph 'gg'");
+    }
+
+    #[test]
+    fn bad_dice_are_rejected() {
+        let json = file(
+            vec![with(goblin(), "damage", json!("lots"))],
+            vec![],
+            vec![],
+            vec![],
+        );
+        assert_rejected(json, "Goblin", "has damage 'lots'");
+    }
+
+    #[test]
+    fn out_of_range_stats_are_rejected() {
+        let json = file(vec![with(goblin(), "hp", json!(0))], vec![], vec![], vec![]);
+        assert_rejected(json, "Goblin", "has hp 0");
+    }
+
+    #[test]
+    fn zero_frequencies_are_rejected() {
+        let json = file(
+            vec![],
+            vec![with(potion(), "frequency", json!(0))],
+            vec![],
+            vec![],
+        );
+        assert_rejected(json, "Healing Potion", "can never spawn");
+    }
+
+    #[test]
+    fn negative_frequencies_are_rejected() {
+        let json = file(
+            vec![with(goblin(), "frequency", json!(-1))],
+            vec![],
+            vec![],
+            vec![],
+        );
+        assert_rejected(json, "Goblin", "has frequency -1");
+    }
+
+    #[test]
+    fn guaranteed_entries_must_spawn_on_levels() {
+        let potion = with(potion(), "frequency", json!(0));
+        let potion = with(potion, "tables", json!({ "vault": 10 }));
+        let potion = with(potion, "guaranteed", json!(1));
+        let json = file(vec![], vec![potion], vec![], vec![]);
+        assert_rejected(json, "Healing Potion", "is guaranteed but never spawns");
+    }
+
+    #[test]
+    fn depths_outside_the_dungeon_are_rejected() {
+        let json = file(
+            vec![with(goblin(), "min_depth", json!(0))],
+            vec![],
+            vec![],
+            vec![],
+        );
+        assert_rejected(json, "Goblin", "has min_depth 0");
+    }
+
+    #[test]
+    fn duplicate_names_are_rejected() {
+        let json = file(vec![goblin(), goblin()], vec![], vec![], vec![]);
+        assert_rejected(json, "Goblin", "is defined more than once");
+    }
+
+    #[test]
+    fn items_that_do_nothing_are_rejected() {
+        let json = file(
+            vec![],
+            vec![with(potion(), "effects", json!([]))],
+            vec![],
+            vec![],
+        );
+        assert_rejected(json, "Healing Potion", "has neither effects nor equipment");
+    }
+
+    #[test]
+    fn aimed_effects_need_a_range() {
+        let potion = with(potion(), "effects", json!([{ "damage": 4 }]));
+        let json = file(vec![], vec![potion], vec![], vec![]);
+        assert_rejected(json, "Healing Potion", "has no range to throw it");
+    }
+
+    #[test]
+    fn bad_status_effects_are_rejected() {
+        let needle = with(needle(), "effect", json!({ "sleep": 0 }));
+        let json = file(vec![], vec![], vec![needle], vec![]);
+        assert_rejected(json, "Poison Needle", "lasting 0 turns");
+    }
+
+    #[test]
+    fn undefined_abilities_are_rejected() {
+        let goblin = with(goblin(), "abilities", json!(["Fireball"]));
+        let json = file(vec![goblin], vec![], vec![], vec![missile()]);
+        assert_rejected(json, "Goblin", "knows the ability 'Fireball'");
+    }
+
+    #[test]
+    fn abilities_need_the_mana_to_cast_them() {
+        let goblin = with(goblin(), "abilities", json!(["Magic Missile"]));
+        let json = file(vec![goblin], vec![], vec![], vec![missile()]);
+        assert_rejected(json, "Goblin", "too little mana");
+    }
+
+    #[test]
+    fn bad_abilities_are_rejected() {
+        let json = file(
+            vec![],
+            vec![],
+            vec![],
+            vec![with(missile(), "effects", json!([]))],
+        );
+        assert_rejected(json, "Magic Missile", "has no effects");
+    }
+}
@@ -0,0 +1,229 @@
+use super::initiative::acting_entities;
+use crate::prelude::*;
+use legion::world::EntityAccessError;
+
+/// Abilities known by `owner` as (ability, name) pairs, sorted by name so the hud and the
+/// cast keys agree on their order
+pub fn known_abilities(ecs: &SubWorld, owner: Entity) -> Vec<(Entity, String)> {
+    let mut abilities: Vec<(Entity, String)> = <(Entity, &Ability, &Name)>::query()
+        .iter(ecs)
+        .filter(|(_, ability, _)| ability.owner == owner)
+        .map(|(entity, _, name)| (*entity, name.0.clone()))
+        .collect();
+
+    abilities.sort_by(|a, b| a.1.cmp(&b.1));
+    abilities
+}
+
+/// Function keys cast an ability, F1 being the first
+pub fn key_to_ability(key: VirtualKeyCode) -> Option<usize> {
+    match key {
+        VirtualKeyCode::F1 => Some(0),
+        VirtualKeyCode::F2 => Some(1),
+        VirtualKeyCode::F3 => Some(2),
+        VirtualKeyCode::F4 => Some(3),
+        VirtualKeyCode::F5 => Some(4),
+        VirtualKeyCode::F6 => Some(5),
+        VirtualKeyCode::F7 => Some(6),
+        VirtualKeyCode::F8 => Some(7),
+        _ => None,
+    }
+}
+
+/// Why `caster` can't cast `ability` right now, None when it can. Systems calling this
+/// need read access to `Ability`, `Mana` and `Name`
+pub fn cast_problem(ecs: &SubWorld, caster: Entity, ability: Entity) -> Option<String> {
+    let entry = ecs.entry_ref(ability).ok()?;
+    let name = entry
+        .get_component::<Name>()
+        .map(|name| name.0.clone())
+        .unwrap_or_default();
+    let ability = match entry.get_component::<Ability>() {
+        Ok(ability) if ability.owner == caster => *ability,
+        _ => return Some(format!("You don't know {}", name)),
+    };
+    let mana = ecs
+        .entry_ref(caster)
+        .ok()
+        .and_then(|entry| entry.get_component::<Mana>().ok().map(|mana| mana.current))
+        .unwrap_or(0);
+
+    if ability.ready_in > 0 {
+        Some(format!("{} is ready in {} turns", name, ability.ready_in))
+    } else if mana < ability.mana {
+        Some(format!("Not enough mana for {}", name))
+    } else {
+        None
+    }
+}
+
+/// Abilities with a range send the player into targeting mode, anything else is cast
+/// straight away. Trying to cast what isn't ready costs nothing. Systems calling this need
+/// read access to `Ability`, `Mana`, `Name`, `Ranged` and `AreaOfEffect`
+pub fn cast_or_aim(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    turn_state: &mut TurnState,
+    targeting: &mut Option<Targeting>,
+    game_log: &mut GameLog,
+    caster: Entity,
+    ability: Entity,
+) {
+    if let Some(problem) = cast_problem(ecs, caster, ability) {
+        game_log.push(problem, GRAY);
+        return;
+    }
+
+    let aimed = ecs.entry_ref(ability).ok().and_then(|entry| {
+        let range = entry.get_component::<Ranged>().ok()?.range;
+        let radius = entry
+            .get_component::<AreaOfEffect>()
+            .map_or(0, |area| area.radius);
+        Some(Targeting::new(Aim::Ability(ability), range, radius))
+    });
+
+    match aimed {
+        Some(aimed) => {
+            *targeting = Some(aimed);
+            *turn_state = TurnState::Targeting;
+        }
+        None => {
+            commands.push((
+                (),
+                WantsToCast {
+                    caster,
+                    ability,
+                    target: None,
+                },
+            ));
+            *turn_state = TurnState::PlayerTurn;
+        }
+    }
+}
+
+/// Pays the mana and starts the cooldown of every ability being cast, then hands it to the
+/// use items system, which applies its effects just like it does for items
+#[system]
+#[read_component(WantsToCast)]
+#[read_component(Player)]
+#[read_component(Name)]
+#[read_component(Point)]
+#[read_component(FieldOfView)]
+#[write_component(Ability)]
+#[write_component(Mana)]
+pub fn casting(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] game_log: &mut GameLog,
+) {
+    let casts: Vec<(Entity, WantsToCast)> = <(Entity, &WantsToCast)>::query()
+        .iter(ecs)
+        .map(|(message, cast)| (*message, *cast))
+        .collect();
+
+    let player_fov = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .cloned();
+
+    casts.into_iter().for_each(|(message, cast)| {
+        commands.remove(message);
+
+        // the caster may have been killed since it made up its mind
+        if cast_problem(ecs, cast.caster, cast.ability).is_some() {
+            return;
+        }
+
+        let (cost, name) = match ecs.entry_mut(cast.ability) {
+            Ok(mut entry) => {
+                let name = entry
+                    .get_component::<Name>()
+                    .map(|name| name.0.clone())
+                    .unwrap_or_default();
+                match entry.get_component_mut::<Ability>() {
+                    Ok(ability) => {
+                        ability.ready_in = ability.cooldown;
+                        (ability.mana, name)
+                    }
+                    Err(_) => return,
+                }
+            }
+            Err(_) => return,
+        };
+
+        if let Ok(mut caster) = ecs.entry_mut(cast.caster) {
+            if let Ok(mana) = caster.get_component_mut::<Mana>() {
+                mana.current -= cost;
+            }
+
+            if caster.get_component::<Player>().is_ok() {
+                game_log.push(format!("You cast {}", name), CYAN);
+            } else {
+                let seen = caster.get_component::<Point>().is_ok_and(|pos| {
+                    player_fov
+                        .as_ref()
+                        .is_some_and(|fov| fov.visible_tiles.contains(pos))
+                });
+                if let (true, Ok(caster_name)) = (seen, caster.get_component::<Name>()) {
+                    game_log.push(format!("The {} casts {}", caster_name.0, name), ORANGE);
+                }
+            }
+        }
+
+        commands.push((
+            (),
+            ActivateItem {
+                used_by: cast.caster,
+                item: cast.ability,
+                target: cast.target,
+            },
+        ));
+    });
+}
+
+/// Cooldowns count down with their owner's actions, the casting one included, and casters
+/// regain a point of mana with each of them. Abilities outlive nobody, they go with their owner
+#[system]
+#[read_component(Player)]
+#[read_component(Energy)]
+#[write_component(Mana)]
+#[write_component(Ability)]
+pub fn cooldowns(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] turn_state: &TurnState,
+) {
+    let acting = acting_entities(ecs, turn_state);
+
+    acting.iter().for_each(|entity| {
+        if let Ok(mut entry) = ecs.entry_mut(*entity) {
+            if let Ok(mana) = entry.get_component_mut::<Mana>() {
+                mana.current = i32::min(mana.max, mana.current + 1);
+            }
+        }
+    });
+
+    let owners: Vec<(Entity, Entity)> = <(Entity, &Ability)>::query()
+        .iter(ecs)
+        .map(|(entity, ability)| (*entity, ability.owner))
+        .collect();
+    let orphans: Vec<Entity> = owners
+        .into_iter()
+        .filter(|(_, owner)| {
+            matches!(
+                ecs.entry_ref(*owner),
+                Err(EntityAccessError::EntityNotFound)
+            )
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+    orphans
+        .into_iter()
+        .for_each(|entity| commands.remove(entity));
+
+    <&mut Ability>::query()
+        .iter_mut(ecs)
+        .filter(|ability| acting.contains(&ability.owner))
+        .for_each(|ability| ability.ready_in = i32::max(0, ability.ready_in - 1));
+}
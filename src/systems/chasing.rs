@@ -1,86 +1,171 @@
+use super::abilities::{cast_problem, known_abilities};
+use super::monster_ai::step_or_attack;
 use crate::prelude::*;
 
+/// Hunting monsters head straight for their target, the player is found along the shared
+/// chase field and anything else is tracked down by a path of its own
 #[system]
 #[read_component(Point)]
-#[read_component(Health)]
-#[read_component(Name)]
 #[read_component(Player)]
-#[read_component(Enemy)]
+#[read_component(AiState)]
+#[read_component(Energy)]
+#[read_component(Health)]
+#[read_component(Faction)]
+#[read_component(Charmed)]
+#[read_component(Provoked)]
+#[read_component(Asleep)]
 #[read_component(FieldOfView)]
-#[read_component(ChasingPlayer)]
-pub fn chasing(ecs: &SubWorld, #[resource] map: &Map, commands: &mut CommandBuffer) {
-    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView)>::query();
-    let mut positions = <(Entity, &Point, &Health)>::query();
-    let mut player = <(&Point, &Player)>::query();
-
-    let player_pos = player.iter(ecs).nth(0).expect("Could not find player").0;
+#[read_component(Ranged)]
+#[read_component(Name)]
+#[read_component(Mana)]
+#[read_component(Ability)]
+#[read_component(ProvidesHealing)]
+#[read_component(InflictsDamage)]
+#[read_component(InflictsStatus)]
+pub fn chasing(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] index: &SpatialIndex,
+    #[resource] fields: &FlowFields,
+    commands: &mut CommandBuffer,
+) {
+    // sleeping monsters are left where they lie
+    let mut movers = <(Entity, &Point, &AiState, &Energy)>::query().filter(!component::<Asleep>());
+    let mut player = <(Entity, &Player)>::query();
 
-    let player_idx = map_idx(player_pos.x, player_pos.y);
-    let search_targets = vec![player_idx];
-    let dijkstra_map = DijkstraMap::new(SCREEN_WIDTH, SCREEN_HEIGHT, &search_targets, map, 1024.0);
+    let player = *player.iter(ecs).next().expect("Could not find player").0;
 
     movers
         .iter(ecs)
-        .filter(|(_, _, _, fov)| fov.visible_tiles.contains(&player_pos))
-        .for_each(|(monster, monster_pos, _, _)| {
+        .filter(|(_, _, _, energy)| energy.is_ready())
+        .for_each(|(monster, monster_pos, state, _)| {
+            let (target, target_pos) = match state {
+                AiState::Hunting { target, last_seen } => (*target, *last_seen),
+                _ => return,
+            };
+
+            // spellcasters try their abilities first
+            if let Some((ability, aim)) =
+                choose_ability(ecs, map, index, *monster, *monster_pos, target)
+            {
+                commands.push((
+                    (),
+                    WantsToCast {
+                        caster: *monster,
+                        ability,
+                        target: aim,
+                    },
+                ));
+                return;
+            }
+
+            // archers shoot from where they stand when they have a clear shot
+            let range = ecs
+                .entry_ref(*monster)
+                .ok()
+                .and_then(|entry| entry.get_component::<Ranged>().ok().map(|r| r.range));
+            let shot = range.and_then(|range| {
+                clear_shot(ecs, map, index, *monster, *monster_pos, target, range)
+            });
+            if let Some(victim_pos) = shot {
+                // up close they hit with whatever they hold
+                if !map.is_step(*monster_pos, victim_pos) {
+                    commands.push((
+                        (),
+                        WantsToAttack {
+                            attacker: *monster,
+                            victim: target,
+                        },
+                    ));
+                    return;
+                }
+            }
+
             let monster_map_idx = map_idx(monster_pos.x, monster_pos.y);
 
-            // find the easiest way to the player
-            if let Some(dest) = DijkstraMap::find_lowest_exit(&dijkstra_map, monster_map_idx, map) {
-                let distance = DistanceAlg::Pythagoras.distance2d(*monster_pos, *player_pos);
+            // find the easiest way to the target
+            let next_step = if target == player {
+                // shared by every chaser and only rebuilt when the player moves
+                fields
+                    .chase()
+                    .and_then(|field| DijkstraMap::find_lowest_exit(field, monster_map_idx, map))
+            } else {
+                let path = a_star_search(monster_map_idx, map_idx(target_pos.x, target_pos.y), map);
+                path.steps.get(1).copied().filter(|_| path.success)
+            };
 
-                // diagonal tile distance is approximately 1.4
-                let dest = if distance < 1.2 {
-                    // move into player
-                    *player_pos
+            if let Some(dest) = next_step {
+                // next to the target, diagonally too with eight-way movement
+                let dest = if map.is_step(*monster_pos, target_pos) {
+                    // move into the target
+                    target_pos
                 } else {
-                    // move towards player
+                    // move towards the target
                     map.index_to_point2d(dest)
                 };
 
-                let mut attacked = false;
-                positions
-                    .iter(ecs)
-                    .filter(|(_, pos, _)| **pos == dest)
-                    .for_each(|(victim, _, _)| {
-                        // we only want to attack the player for now
-                        if ecs
-                            .entry_ref(*victim)
-                            .unwrap()
-                            .get_component::<Player>()
-                            .is_ok()
-                        {
-                            attacked = true;
-                            commands.push((
-                                (),
-                                WantsToAttack {
-                                    victim: *victim,
-                                    attacker: *monster,
-                                },
-                            ));
-                        }
+                step_or_attack(ecs, index, commands, *monster, dest);
+            }
+        });
+}
 
-                        if !attacked
-                            && ecs
-                                .entry_ref(*victim)
-                                .unwrap()
-                                .get_component::<Enemy>()
-                                .is_ok()
-                        {
-                            println!("Will move into another monster!");
-                            attacked = true;
-                        }
-                    });
+/// An ability worth casting right now, with the tile to aim it at: healing when badly hurt,
+/// anything harmful that has a clear shot at the target otherwise
+fn choose_ability(
+    ecs: &SubWorld,
+    map: &Map,
+    index: &SpatialIndex,
+    monster: Entity,
+    monster_pos: Point,
+    target: Entity,
+) -> Option<(Entity, Option<Point>)> {
+    let hurt = ecs
+        .entry_ref(monster)
+        .ok()?
+        .get_component::<Health>()
+        .is_ok_and(|health| health.current * 2 <= health.max);
 
-                if !attacked {
-                    commands.push((
-                        (),
-                        WantsToMove {
-                            entity: *monster,
-                            destination: dest,
-                        },
-                    ));
-                }
+    known_abilities(ecs, monster)
+        .into_iter()
+        .filter(|(ability, _)| cast_problem(ecs, monster, *ability).is_none())
+        .find_map(|(ability, _)| {
+            let entry = ecs.entry_ref(ability).ok()?;
+            if entry.get_component::<ProvidesHealing>().is_ok() {
+                return if hurt { Some((ability, None)) } else { None };
             }
-        });
+
+            let harmful = entry.get_component::<InflictsDamage>().is_ok()
+                || entry.get_component::<InflictsStatus>().is_ok();
+            let range = entry.get_component::<Ranged>().ok()?.range;
+            if !harmful {
+                return None;
+            }
+            clear_shot(ecs, map, index, monster, monster_pos, target, range)
+                .map(|target_pos| (ability, Some(target_pos)))
+        })
+}
+
+/// Where the target stands when the monster can see it and it is within `range` with
+/// nothing in the way, the same checks the player's targeting makes
+fn clear_shot(
+    ecs: &SubWorld,
+    map: &Map,
+    index: &SpatialIndex,
+    monster: Entity,
+    monster_pos: Point,
+    target: Entity,
+    range: i32,
+) -> Option<Point> {
+    let shooter = ecs.entry_ref(monster).ok()?;
+    let fov = shooter.get_component::<FieldOfView>().ok()?;
+    let target_pos = *ecs.entry_ref(target).ok()?.get_component::<Point>().ok()?;
+
+    if fov.visible_tiles.contains(&target_pos)
+        && DistanceAlg::Pythagoras.distance2d(monster_pos, target_pos) <= range as f32
+        && has_line_of_fire(map, index, monster_pos, target_pos)
+    {
+        Some(target_pos)
+    } else {
+        None
+    }
 }
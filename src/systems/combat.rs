@@ -1,39 +1,211 @@
+use super::equipment::{effective_attack, effective_defense};
+use super::faction::reaction;
+use super::status_effects::inflict;
 use crate::prelude::*;
 
+/// a natural 1 always misses, a natural 20 always hits and doubles the damage
+const HIT_DIE: i32 = 20;
+const HIT_TARGET: i32 = 10;
+
 #[system]
 #[read_component(WantsToAttack)]
 #[read_component(Player)]
+#[read_component(Name)]
+#[read_component(Attack)]
+#[read_component(Defense)]
+#[read_component(Equipped)]
+#[read_component(AttackBonus)]
+#[read_component(DefenseBonus)]
+#[read_component(Point)]
+#[read_component(BlocksTile)]
+#[read_component(FieldOfView)]
+#[read_component(Faction)]
+#[read_component(Charmed)]
+#[read_component(Provoked)]
+#[read_component(ExperienceValue)]
+#[read_component(InflictsStatus)]
+#[read_component(Poisoned)]
+#[read_component(Confused)]
+#[read_component(Asleep)]
+#[read_component(Afraid)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
 #[write_component(Health)]
-pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+#[write_component(Experience)]
+pub fn combat(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] game_log: &mut GameLog,
+    #[resource] index: &mut SpatialIndex,
+) {
     // look for attack messages
     let mut attackers = <(Entity, &WantsToAttack)>::query();
 
-    let victims: Vec<(Entity, Entity)> = attackers
+    let attacks: Vec<(Entity, Entity, Entity)> = attackers
         .iter(ecs)
-        .map(|(attack_entity, attack)| (*attack_entity, attack.victim))
+        .map(|(attack_entity, attack)| (*attack_entity, attack.attacker, attack.victim))
         .collect();
 
-    victims.iter().for_each(|(message, victim)| {
-        // check if the player died
-        let is_player = ecs
-            .entry_ref(*victim)
-            .unwrap()
-            .get_component::<Player>()
-            .is_ok();
-
-        // look for health component
-        if let Ok(mut health) = ecs
-            .entry_mut(*victim)
-            .unwrap()
-            .get_component_mut::<Health>()
-        {
-            health.current -= 1;
-            if health.current < 1 && !is_player {
-                // killed them
-                commands.remove(*victim)
+    // fights between monsters are only reported when the player sees them
+    let player_fov = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .cloned();
+
+    attacks.iter().for_each(|(message, attacker, victim)| {
+        commands.remove(*message);
+
+        let attacker_name = match ecs.entry_ref(*attacker) {
+            Ok(entry) => name_of(&entry),
+            // the attacker was removed before it could strike
+            Err(_) => return,
+        };
+
+        let (victim_name, is_player, victim_pos, asleep) = match ecs.entry_ref(*victim) {
+            // somebody else got to it first this turn
+            Ok(entry) if entry.get_component::<Health>().is_ok_and(|h| h.current < 1) => return,
+            Ok(entry) => (
+                name_of(&entry),
+                // check if the player died
+                entry.get_component::<Player>().is_ok(),
+                entry.get_component::<Point>().ok().copied(),
+                entry.get_component::<Asleep>().is_ok(),
+            ),
+            Err(_) => return,
+        };
+        let involves_player = is_player
+            || ecs
+                .entry_ref(*attacker)
+                .is_ok_and(|entry| entry.get_component::<Player>().is_ok());
+        let seen = involves_player
+            || victim_pos.is_some_and(|pos| {
+                player_fov
+                    .as_ref()
+                    .is_some_and(|fov| fov.visible_tiles.contains(&pos))
+            });
+
+        // attacking a creature that did not want a fight makes it fight back
+        if !is_player && reaction(ecs, *victim, *attacker) != Reaction::Hostile {
+            commands.add_component(*victim, Provoked { by: *attacker });
+            if seen {
+                game_log.push(format!("The {} is provoked", victim_name), ORANGE);
             }
         }
 
-        commands.remove(*message);
+        let attack = effective_attack(ecs, *attacker);
+        let defense = effective_defense(ecs, *victim);
+
+        let roll = rng.roll_dice(1, HIT_DIE);
+        let outcome = if roll == HIT_DIE {
+            CombatOutcome::Critical
+        } else if roll == 1 || roll + attack.to_hit < HIT_TARGET + defense.armour {
+            CombatOutcome::Miss
+        } else {
+            CombatOutcome::Hit
+        };
+
+        let damage = match outcome {
+            CombatOutcome::Miss => 0,
+            CombatOutcome::Hit => i32::max(1, rng.roll(attack.damage)),
+            CombatOutcome::Critical => {
+                i32::max(1, rng.roll(attack.damage) + rng.roll(attack.damage))
+            }
+        };
+
+        // nobody sleeps through being attacked
+        if asleep {
+            commands.remove_component::<Asleep>(*victim);
+            if seen {
+                let text = if is_player {
+                    "You wake up".to_string()
+                } else {
+                    format!("The {} wakes up", victim_name)
+                };
+                game_log.push(text, GRAY);
+            }
+        }
+
+        let killed = apply_damage(ecs, commands, index, Some(*attacker), *victim, damage);
+
+        if seen {
+            game_log.push_combat(&CombatEvent {
+                attacker: attacker_name,
+                victim: victim_name,
+                outcome,
+                damage,
+                killed,
+            });
+        }
+
+        // venomous bites and the like only work when they hit something that lives on
+        let inflicts = ecs
+            .entry_ref(*attacker)
+            .ok()
+            .and_then(|entry| entry.get_component::<InflictsStatus>().ok().copied());
+        if let Some(inflicts) = inflicts {
+            if damage > 0 && !killed && rng.range(0, 100) < inflicts.chance {
+                inflict(ecs, commands, game_log, *victim, inflicts.effect, seen);
+            }
+        }
     });
 }
+
+/// Takes `damage` off the victim's health, a monster brought down is removed and its
+/// experience goes to the attacker, when there is one rather than say lava. True when the
+/// victim was killed. Systems calling this need write access to `Health` and `Experience`,
+/// and read access to `Player`, `Point`, `BlocksTile` and `ExperienceValue`
+pub fn apply_damage(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    index: &mut SpatialIndex,
+    attacker: Option<Entity>,
+    victim: Entity,
+    damage: i32,
+) -> bool {
+    let mut entry = match ecs.entry_mut(victim) {
+        Ok(entry) => entry,
+        Err(_) => return false,
+    };
+    let is_player = entry.get_component::<Player>().is_ok();
+
+    let mut killed = false;
+    // look for health component
+    if let Ok(health) = entry.get_component_mut::<Health>() {
+        health.current -= damage;
+        // the end turn system takes care of a dead player
+        if health.current < 1 && !is_player {
+            // killed them
+            killed = true;
+            commands.remove(victim)
+        }
+    }
+    if !killed {
+        return false;
+    }
+
+    // the tile is free again for whoever moves next
+    if let Ok(pos) = entry.get_component::<Point>() {
+        index.remove(victim, *pos, entry.get_component::<BlocksTile>().is_ok());
+    }
+    let reward = entry
+        .get_component::<ExperienceValue>()
+        .map_or(0, |value| value.xp);
+
+    // the levelling system takes care of spending it
+    if let Some(Ok(mut entry)) = attacker.map(|attacker| ecs.entry_mut(attacker)) {
+        if let Ok(experience) = entry.get_component_mut::<Experience>() {
+            experience.xp += reward;
+        }
+    }
+
+    true
+}
+
+fn name_of(entry: &legion::world::EntryRef) -> String {
+    entry
+        .get_component::<Name>()
+        .map(|name| name.0.clone())
+        .unwrap_or_else(|_| "Something".to_string())
+}
@@ -0,0 +1,30 @@
+use crate::prelude::*;
+
+#[system(for_each)]
+#[read_component(Point)]
+#[read_component(Name)]
+pub fn drop_item(
+    message_entity: &Entity,
+    drop: &WantsToDrop,
+    ecs: &SubWorld,
+    #[resource] game_log: &mut GameLog,
+    commands: &mut CommandBuffer,
+) {
+    // the item lands where whoever carried it is standing
+    if let Ok(pos) = ecs
+        .entry_ref(drop.dropped_by)
+        .unwrap()
+        .get_component::<Point>()
+    {
+        // dropped gear is no longer worn
+        commands.remove_component::<Carried>(drop.item);
+        commands.remove_component::<Equipped>(drop.item);
+        commands.add_component(drop.item, *pos);
+
+        if let Ok(name) = ecs.entry_ref(drop.item).unwrap().get_component::<Name>() {
+            game_log.push(format!("You drop the {}", name.0), CYAN);
+        }
+    }
+
+    commands.remove(*message_entity);
+}
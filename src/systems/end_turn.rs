@@ -1,23 +1,20 @@
 use crate::prelude::*;
 
+/// Whoever acts next was decided by the initiative system, the player dying or taking
+/// the stairs overrides that
 #[system]
 #[read_component(Player)]
 #[read_component(Health)]
-pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState) {
-    let mut player_hp = <&Health>::query().filter(component::<Player>());
+#[read_component(Point)]
+pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resource] map: &Map) {
+    let mut player = <(&Health, &Point)>::query().filter(component::<Player>());
 
-    let mut next_turn = match turn_state {
-        TurnState::AwaitingInput => return,
-        TurnState::PlayerTurn => TurnState::MonsterTurn,
-        TurnState::MonsterTurn => TurnState::AwaitingInput,
-        _ => *turn_state,
-    };
-
-    player_hp.iter(ecs).for_each(|hp| {
+    player.iter(ecs).for_each(|(hp, pos)| {
         if hp.current < 1 {
-            next_turn = TurnState::GameOver;
+            *turn_state = TurnState::GameOver;
+        } else if map.tiles[map_idx(pos.x, pos.y)] == TileType::Exit {
+            // taking the stairs down
+            *turn_state = TurnState::NextLevel;
         }
     });
-
-    *turn_state = next_turn;
 }
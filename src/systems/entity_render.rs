@@ -5,24 +5,30 @@ use crate::prelude::*;
 #[read_component(Render)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
+#[read_component(Trap)]
 pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
     // get player field of view
     let player_fov = <&FieldOfView>::query()
         .filter(component::<Player>())
         .iter(ecs)
-        .nth(0)
+        .next()
         .unwrap();
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(1);
 
     let offset = Point::new(camera.left_x, camera.top_y);
-    <(&Point, &Render)>::query()
+    // traps go first, anything standing on one is drawn on top of it
+    let mut visible: Vec<(&Point, &Render, bool)> = <(&Point, &Render, Option<&Trap>)>::query()
         .iter(ecs)
-        .filter(|(pos, _)| player_fov.visible_tiles.contains(pos))
-        .for_each(|(point, render)| {
-            draw_batch.set(*point - offset, render.color, render.glyph);
-        });
+        .filter(|(pos, _, _)| player_fov.visible_tiles.contains(pos))
+        .map(|(pos, render, trap)| (pos, render, trap.is_some()))
+        .collect();
+    visible.sort_by_key(|(_, _, trap)| !trap);
+
+    visible.iter().for_each(|(point, render, _)| {
+        draw_batch.set(**point - offset, render.color, render.glyph);
+    });
 
     draw_batch
         .submit(5000)
@@ -0,0 +1,61 @@
+use crate::prelude::*;
+
+use legion::world::EntryRef;
+
+/// The base attack of an entity with the bonuses of everything it has equipped
+pub fn effective_attack(ecs: &SubWorld, entity: Entity) -> Attack {
+    let mut attack = ecs
+        .entry_ref(entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Attack>().ok().copied())
+        .unwrap_or_default();
+
+    <(&Equipped, &AttackBonus)>::query()
+        .iter(ecs)
+        .filter(|(equipped, _)| equipped.owner == entity)
+        .for_each(|(_, bonus)| {
+            attack.to_hit += bonus.to_hit;
+            attack.damage.bonus += bonus.damage;
+        });
+
+    attack
+}
+
+/// The base defense of an entity with the bonuses of everything it has equipped
+pub fn effective_defense(ecs: &SubWorld, entity: Entity) -> Defense {
+    let mut defense = ecs
+        .entry_ref(entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Defense>().ok().copied())
+        .unwrap_or_default();
+
+    <(&Equipped, &DefenseBonus)>::query()
+        .iter(ecs)
+        .filter(|(equipped, _)| equipped.owner == entity)
+        .for_each(|(_, bonus)| defense.armour += bonus.armour);
+
+    defense
+}
+
+/// What an item in a slot would give, e.g. "weapon +1 hit +2 dmg", None for non equipment
+pub fn equipment_summary(entry: &EntryRef) -> Option<String> {
+    let equippable = entry.get_component::<Equippable>().ok()?;
+    let mut summary = equippable.slot.name().to_string();
+
+    if let Ok(bonus) = entry.get_component::<AttackBonus>() {
+        summary.push_str(&format!(" {:+} hit {:+} dmg", bonus.to_hit, bonus.damage));
+    }
+    if let Ok(bonus) = entry.get_component::<DefenseBonus>() {
+        summary.push_str(&format!(" {:+} armour", bonus.armour));
+    }
+
+    Some(summary)
+}
+
+/// The item `owner` has equipped in `slot`, if any
+pub fn equipped_in(ecs: &SubWorld, owner: Entity, slot: EquipmentSlot) -> Option<Entity> {
+    <(Entity, &Equipped)>::query()
+        .iter(ecs)
+        .find(|(_, equipped)| equipped.owner == owner && equipped.slot == slot)
+        .map(|(entity, _)| *entity)
+}
@@ -0,0 +1,34 @@
+use crate::prelude::*;
+
+/// The side a creature fights for, charmed creatures side with whoever charmed them
+fn faction_of(ecs: &SubWorld, entity: Entity) -> Option<Faction> {
+    let entry = ecs.entry_ref(entity).ok()?;
+
+    let side = match entry.get_component::<Charmed>() {
+        Ok(charmed) => ecs.entry_ref(charmed.by).ok()?,
+        Err(_) => entry,
+    };
+
+    side.get_component::<Faction>().ok().copied()
+}
+
+/// How `from` treats `to`, being provoked trumps the reaction table. Systems calling this
+/// need read access to `Faction`, `Charmed` and `Provoked`
+pub fn reaction(ecs: &SubWorld, from: Entity, to: Entity) -> Reaction {
+    if from == to {
+        return Reaction::Friendly;
+    }
+
+    let provoked = ecs
+        .entry_ref(from)
+        .ok()
+        .and_then(|entry| entry.get_component::<Provoked>().ok().copied());
+    if provoked.is_some_and(|provoked| provoked.by == to) {
+        return Reaction::Hostile;
+    }
+
+    match (faction_of(ecs, from), faction_of(ecs, to)) {
+        (Some(from), Some(to)) => from.reaction_to(to),
+        _ => Reaction::Neutral,
+    }
+}
@@ -0,0 +1,45 @@
+use super::monster_ai::step_or_attack;
+use crate::prelude::*;
+
+/// Fleeing monsters follow the shared flee field away from the player, when cornered
+/// it leads them past the player and they lash out instead
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(AiState)]
+#[read_component(Energy)]
+#[read_component(Health)]
+#[read_component(Faction)]
+#[read_component(Charmed)]
+#[read_component(Provoked)]
+#[read_component(Asleep)]
+pub fn fleeing(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] index: &SpatialIndex,
+    #[resource] fields: &mut FlowFields,
+    commands: &mut CommandBuffer,
+) {
+    let fleeing: Vec<(Entity, Point)> = <(Entity, &Point, &AiState, &Energy)>::query()
+        .filter(!component::<Asleep>())
+        .iter(ecs)
+        .filter(|(_, _, state, energy)| energy.is_ready() && **state == AiState::Fleeing)
+        .map(|(monster, pos, _, _)| (*monster, *pos))
+        .collect();
+    if fleeing.is_empty() {
+        return;
+    }
+
+    fields.refresh_flee(map);
+    let flee_map = match fields.flee() {
+        Some(field) => field,
+        None => return,
+    };
+
+    fleeing.into_iter().for_each(|(monster, pos)| {
+        let idx = map_idx(pos.x, pos.y);
+        if let Some(dest) = DijkstraMap::find_lowest_exit(flee_map, idx, map) {
+            step_or_attack(ecs, index, commands, monster, map.index_to_point2d(dest));
+        }
+    });
+}
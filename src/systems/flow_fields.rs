@@ -0,0 +1,25 @@
+use crate::prelude::*;
+
+/// Brings the shared flow fields up to date before the monsters look at them, this is
+/// cheap unless the player moved, an item was moved or the map changed
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Item)]
+pub fn flow_fields(ecs: &SubWorld, #[resource] map: &Map, #[resource] fields: &mut FlowFields) {
+    let player = <&Point>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .copied()
+        .expect("Could not find player");
+
+    // carried items have no position, only those lying around count
+    let items: Vec<Point> = <&Point>::query()
+        .filter(component::<Item>())
+        .iter(ecs)
+        .copied()
+        .collect();
+
+    fields.refresh(map, player, &items);
+}
@@ -0,0 +1,54 @@
+use crate::prelude::*;
+
+/// Show the whole game log full screen, the arrow and page keys scroll
+/// back through older messages, escape returns to the game
+#[system]
+pub fn history(
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] game_log: &mut GameLog,
+) {
+    let page = (SCREEN_HEIGHT * 2 - 6) as usize;
+    let max_scroll = game_log.entries.len().saturating_sub(page);
+
+    if let Some(key) = key {
+        match key {
+            VirtualKeyCode::Up => game_log.scroll += 1,
+            VirtualKeyCode::Down => game_log.scroll = game_log.scroll.saturating_sub(1),
+            VirtualKeyCode::PageUp => game_log.scroll += page,
+            VirtualKeyCode::PageDown => game_log.scroll = game_log.scroll.saturating_sub(page),
+            VirtualKeyCode::Escape | VirtualKeyCode::V => {
+                game_log.scroll = 0;
+                *turn_state = TurnState::AwaitingInput;
+            }
+            _ => {}
+        }
+    }
+    game_log.scroll = game_log.scroll.min(max_scroll);
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(3);
+
+    draw_batch.print_color_centered(1, "Message history", ColorPair::new(YELLOW, BLACK));
+
+    let end = game_log.entries.len() - game_log.scroll;
+    let start = end.saturating_sub(page);
+    game_log.entries[start..end]
+        .iter()
+        .enumerate()
+        .for_each(|(line, entry)| {
+            draw_batch.print_color(
+                Point::new(2, 3 + line as i32),
+                format!("[{}] {}", entry.turn, entry.text),
+                ColorPair::new(entry.color, BLACK),
+            );
+        });
+
+    draw_batch.print_color_centered(
+        SCREEN_HEIGHT * 2 - 2,
+        "Up/Down and PgUp/PgDn to scroll, ESC to return",
+        ColorPair::new(GRAY, BLACK),
+    );
+
+    draw_batch.submit(0).expect("Error rendering history");
+}
@@ -1,16 +1,54 @@
+use super::abilities::known_abilities;
+use super::equipment::{effective_attack, effective_defense, equipped_in};
+use super::initiative::effective_speed;
+use super::inventory::carried_items;
+use super::status_effects::active_effects;
 use crate::prelude::*;
 
+/// How many log entries are shown at the bottom of the screen
+const LOG_LINES: usize = 6;
+
 #[system]
 #[read_component(Health)]
-pub fn hud(ecs: &SubWorld) {
-    let mut health_query = <&Health>::query().filter(component::<Player>());
+#[read_component(Player)]
+#[read_component(Item)]
+#[read_component(Carried)]
+#[read_component(Name)]
+#[read_component(Attack)]
+#[read_component(Defense)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(AttackBonus)]
+#[read_component(DefenseBonus)]
+#[read_component(Energy)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+#[read_component(Poisoned)]
+#[read_component(Confused)]
+#[read_component(Asleep)]
+#[read_component(Afraid)]
+#[read_component(Experience)]
+#[read_component(Mana)]
+#[read_component(Ability)]
+pub fn hud(
+    ecs: &SubWorld,
+    #[resource] depth: &Depth,
+    #[resource] options: &GameOptions,
+    #[resource] game_log: &GameLog,
+    #[resource] turn_state: &TurnState,
+) {
+    let mut health_query = <(Entity, &Health)>::query().filter(component::<Player>());
 
-    let player_health = health_query.iter(ecs).nth(0).unwrap(); // we know about only one player
+    // we know about only one player
+    let (player, player_health) = health_query.iter(ecs).next().unwrap();
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(3);
 
-    draw_batch.print_centered(1, "Explore the dungeon, use cursor keys to navigate");
+    draw_batch.print_centered(
+        1,
+        "Explore the dungeon, move with the arrows, numpad or vi keys, (G)et, (F)ire, (F1-F8) Cast, (I)nventory, (D)rop, (V)iew history",
+    );
     draw_batch.bar_horizontal(
         Point::zero(),
         SCREEN_WIDTH * 2,
@@ -23,6 +61,183 @@ pub fn hud(ecs: &SubWorld) {
         format!(" Health {} / {} ", player_health.current, player_health.max),
         ColorPair::new(WHITE, BLACK),
     );
+    draw_batch.print_color(
+        Point::new(1, 1),
+        format!("Seed {}", options.seed),
+        ColorPair::new(GRAY, BLACK),
+    );
+    draw_batch.print_color_right(
+        Point::new(SCREEN_WIDTH * 2, 1),
+        format!("Depth {} / {}", depth.0, FINAL_DEPTH),
+        ColorPair::new(YELLOW, BLACK),
+    );
+    if let Some(experience) = ecs
+        .entry_ref(*player)
+        .ok()
+        .and_then(|entry| entry.get_component::<Experience>().ok().copied())
+    {
+        draw_batch.print_color_right(
+            Point::new(SCREEN_WIDTH * 2, 2),
+            format!(
+                "Level {}  XP {} / {}",
+                experience.level,
+                experience.xp,
+                experience.next_level()
+            ),
+            ColorPair::new(GOLD, BLACK),
+        );
+    }
+
+    let mana = ecs
+        .entry_ref(*player)
+        .ok()
+        .and_then(|entry| entry.get_component::<Mana>().ok().copied());
+    if let Some(mana) = mana {
+        draw_batch.print_color(
+            Point::new(1, 2),
+            format!("Mana {} / {}", mana.current, mana.max),
+            ColorPair::new(CYAN, BLACK),
+        );
+    }
+
+    // combat stats including everything worn, followed by what is worn
+    let x = SCREEN_WIDTH * 2 - 50;
+    let attack = effective_attack(ecs, *player);
+    let defense = effective_defense(ecs, *player);
+    draw_batch.print_color(
+        Point::new(x, 3),
+        format!(
+            "Attack {:+} ({}d{}{:+})  Armour {}",
+            attack.to_hit,
+            attack.damage.n_dice,
+            attack.damage.die_type,
+            attack.damage.bonus,
+            defense.armour
+        ),
+        ColorPair::new(YELLOW, BLACK),
+    );
+    EquipmentSlot::ALL
+        .iter()
+        .enumerate()
+        .for_each(|(line, slot)| {
+            let worn = equipped_in(ecs, *player, *slot)
+                .and_then(|item| ecs.entry_ref(item).ok())
+                .and_then(|entry| entry.get_component::<Name>().ok().map(|n| n.0.clone()))
+                .unwrap_or_else(|| "-".to_string());
+
+            draw_batch.print(
+                Point::new(x, 4 + line as i32),
+                format!("{:>7}: {}", slot.name(), worn),
+            );
+        });
+
+    // timed status effects, with how many actions they last
+    if let Ok(entry) = ecs.entry_ref(*player) {
+        let status: Vec<String> = active_effects(&entry)
+            .iter()
+            .map(|(effect, turns)| format!("{} {}", effect, turns))
+            .collect();
+        if !status.is_empty() {
+            draw_batch.print_color(
+                Point::new(x, 2),
+                status.join("  "),
+                ColorPair::new(CYAN, BLACK),
+            );
+        }
+    }
+
+    // whose turn it is and how much energy everyone has saved up
+    if options.debug {
+        let (energy, speed) = <(&Energy, Option<&Hasted>, Option<&Slowed>)>::query()
+            .filter(component::<Player>())
+            .iter(ecs)
+            .next()
+            .map_or((0, 0), |(energy, hasted, slowed)| {
+                (energy.current, effective_speed(energy, hasted, slowed))
+            });
+        let ready = <&Energy>::query()
+            .filter(!component::<Player>())
+            .iter(ecs)
+            .filter(|energy| energy.is_ready())
+            .count();
+
+        draw_batch.print_color(
+            Point::new(1, 3),
+            format!("{:?}", turn_state),
+            ColorPair::new(MAGENTA, BLACK),
+        );
+        draw_batch.print_color(
+            Point::new(1, 4),
+            format!("Energy {} / {}, speed {}", energy, ACTION_COST, speed),
+            ColorPair::new(MAGENTA, BLACK),
+        );
+        draw_batch.print_color(
+            Point::new(1, 5),
+            format!("Monsters ready {}", ready),
+            ColorPair::new(MAGENTA, BLACK),
+        );
+    }
+
+    // a short list of what the player carries, numbers match the quick use keys
+    let items = carried_items(ecs, *player);
+    if !items.is_empty() {
+        draw_batch.print_color(
+            Point::new(x, 9),
+            "Items carried",
+            ColorPair::new(YELLOW, BLACK),
+        );
+        items.iter().enumerate().for_each(|(slot, (_, name))| {
+            draw_batch.print(
+                Point::new(x, 10 + slot as i32),
+                format!("{}: {}", slot + 1, name),
+            );
+        });
+    }
+
+    // abilities with their cast keys, greyed out while they can't be cast
+    let abilities = known_abilities(ecs, *player);
+    if !abilities.is_empty() {
+        draw_batch.print_color(Point::new(1, 7), "Abilities", ColorPair::new(YELLOW, BLACK));
+        abilities
+            .iter()
+            .enumerate()
+            .for_each(|(slot, (entity, name))| {
+                let ability = match ecs
+                    .entry_ref(*entity)
+                    .ok()
+                    .and_then(|entry| entry.get_component::<Ability>().ok().copied())
+                {
+                    Some(ability) => ability,
+                    None => return,
+                };
+                let ready =
+                    ability.ready_in == 0 && mana.is_some_and(|mana| mana.current >= ability.mana);
+                let waiting = if ability.ready_in > 0 {
+                    format!(" [{}]", ability.ready_in)
+                } else {
+                    String::new()
+                };
+
+                draw_batch.print_color(
+                    Point::new(1, 8 + slot as i32),
+                    format!("F{}: {} ({} mana){}", slot + 1, name, ability.mana, waiting),
+                    ColorPair::new(if ready { WHITE } else { GRAY }, BLACK),
+                );
+            });
+    }
+
+    // the latest messages, newest at the bottom
+    game_log
+        .recent(LOG_LINES)
+        .iter()
+        .enumerate()
+        .for_each(|(line, entry)| {
+            draw_batch.print_color(
+                Point::new(1, SCREEN_HEIGHT * 2 - LOG_LINES as i32 - 1 + line as i32),
+                format!("[{}] {}", entry.turn, entry.text),
+                ColorPair::new(entry.color, BLACK),
+            );
+        });
 
     draw_batch.submit(10000).expect("Drawing hud error");
 }
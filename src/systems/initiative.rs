@@ -0,0 +1,89 @@
+use crate::prelude::*;
+
+/// The energy an entity gains every tick, haste and slow cancel each other out
+pub fn effective_speed(energy: &Energy, hasted: Option<&Hasted>, slowed: Option<&Slowed>) -> i32 {
+    let hasted = hasted.is_some_and(|hasted| hasted.turns > 0);
+    let slowed = slowed.is_some_and(|slowed| slowed.turns > 0);
+
+    let speed = match (hasted, slowed) {
+        (true, false) => energy.speed * 2,
+        (false, true) => energy.speed / 2,
+        _ => energy.speed,
+    };
+
+    // even the slowest monster gets a move eventually
+    i32::max(1, speed)
+}
+
+/// Whoever pays for an action at the end of this turn, the player after their own turn
+/// and every ready monster after a monster turn
+pub fn acting_entities(ecs: &SubWorld, turn_state: &TurnState) -> Vec<Entity> {
+    let player_acted = match turn_state {
+        TurnState::PlayerTurn => true,
+        TurnState::MonsterTurn => false,
+        _ => return Vec::new(),
+    };
+
+    <(Entity, &Energy, Option<&Player>)>::query()
+        .iter(ecs)
+        .filter(|(_, energy, player)| player.is_some() == player_acted && energy.is_ready())
+        .map(|(entity, _, _)| *entity)
+        .collect()
+}
+
+/// Pays for the actions just taken, then winds the clock forward until somebody can act.
+/// Ready monsters go before the player, so monsters as fast as the player still
+/// alternate with them exactly like they used to
+#[system]
+#[read_component(Player)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+#[write_component(Energy)]
+pub fn initiative(
+    ecs: &mut SubWorld,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] game_log: &mut GameLog,
+) {
+    if !matches!(turn_state, TurnState::PlayerTurn | TurnState::MonsterTurn) {
+        return;
+    }
+
+    // timed effects were already counted down by the status effects system
+    acting_entities(ecs, turn_state)
+        .into_iter()
+        .for_each(|entity| {
+            if let Ok(energy) = ecs.entry_mut(entity).unwrap().get_component_mut::<Energy>() {
+                energy.current -= ACTION_COST;
+            }
+        });
+
+    loop {
+        let monster_ready = <&Energy>::query()
+            .filter(!component::<Player>())
+            .iter(ecs)
+            .any(|energy| energy.is_ready());
+
+        if monster_ready {
+            *turn_state = TurnState::MonsterTurn;
+            return;
+        }
+
+        let player_ready = <&Energy>::query()
+            .filter(component::<Player>())
+            .iter(ecs)
+            .next()
+            .is_none_or(|energy| energy.is_ready());
+
+        if player_ready {
+            game_log.turn += 1;
+            *turn_state = TurnState::AwaitingInput;
+            return;
+        }
+
+        <(&mut Energy, Option<&Hasted>, Option<&Slowed>)>::query()
+            .iter_mut(ecs)
+            .for_each(|(energy, hasted, slowed)| {
+                energy.current += effective_speed(energy, hasted, slowed);
+            });
+    }
+}
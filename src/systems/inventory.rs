@@ -0,0 +1,140 @@
+use super::equipment::equipment_summary;
+use super::targeting::activate_or_aim;
+use crate::prelude::*;
+
+/// Items carried by `owner` as (item, label) pairs, sorted by name so every
+/// system lists them (and numbers them) in the same order
+pub fn carried_items(ecs: &SubWorld, owner: Entity) -> Vec<(Entity, String)> {
+    let mut items: Vec<(Entity, String)> = <(Entity, &Carried, &Name)>::query()
+        .filter(component::<Item>())
+        .iter(ecs)
+        .filter(|(_, carried, _)| carried.0 == owner)
+        .map(|(entity, _, name)| (*entity, name.0.clone()))
+        .collect();
+
+    items.sort_by(|a, b| a.1.cmp(&b.1));
+
+    // gear shows what it is good for, and whether it is being worn
+    items
+        .into_iter()
+        .map(|(entity, name)| {
+            let entry = ecs.entry_ref(entity).unwrap();
+            let label = match equipment_summary(&entry) {
+                Some(summary) if entry.get_component::<Equipped>().is_ok() => {
+                    format!("{} ({}) [equipped]", name, summary)
+                }
+                Some(summary) => format!("{} ({})", name, summary),
+                None => name,
+            };
+
+            (entity, label)
+        })
+        .collect()
+}
+
+/// The pack holds as many items as there are number keys to pick them with
+pub const MAX_CARRIED: usize = 9;
+
+/// Number keys select an inventory slot, 1 being the first item
+pub fn key_to_slot(key: VirtualKeyCode) -> Option<usize> {
+    match key {
+        VirtualKeyCode::Key1 => Some(0),
+        VirtualKeyCode::Key2 => Some(1),
+        VirtualKeyCode::Key3 => Some(2),
+        VirtualKeyCode::Key4 => Some(3),
+        VirtualKeyCode::Key5 => Some(4),
+        VirtualKeyCode::Key6 => Some(5),
+        VirtualKeyCode::Key7 => Some(6),
+        VirtualKeyCode::Key8 => Some(7),
+        VirtualKeyCode::Key9 => Some(8),
+        _ => None,
+    }
+}
+
+/// Shows the inventory window, a number activates (or drops) an item and
+/// ends the players turn, escape closes the window
+#[system]
+#[read_component(Player)]
+#[read_component(Item)]
+#[read_component(Carried)]
+#[read_component(Name)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(AttackBonus)]
+#[read_component(DefenseBonus)]
+#[read_component(Ranged)]
+#[read_component(AreaOfEffect)]
+pub fn inventory(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] targeting: &mut Option<Targeting>,
+) {
+    let player = *<Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    let items = carried_items(ecs, player);
+    let dropping = *turn_state == TurnState::DroppingItem;
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(3);
+
+    let width = 60;
+    let height = items.len().max(1) as i32 + 3;
+    let x = SCREEN_WIDTH - width / 2;
+    let y = 10;
+
+    draw_batch.draw_double_box(
+        Rect::with_size(x, y, width, height),
+        ColorPair::new(WHITE, BLACK),
+    );
+    draw_batch.print_color(
+        Point::new(x + 2, y),
+        if dropping {
+            " Drop which item? "
+        } else {
+            " Inventory "
+        },
+        ColorPair::new(YELLOW, BLACK),
+    );
+    draw_batch.print_color(
+        Point::new(x + 2, y + height),
+        " 1-9 to select, ESC to close ",
+        ColorPair::new(GRAY, BLACK),
+    );
+
+    if items.is_empty() {
+        draw_batch.print(Point::new(x + 2, y + 2), "You are not carrying anything");
+    }
+
+    items.iter().enumerate().for_each(|(slot, (_, name))| {
+        draw_batch.print(
+            Point::new(x + 2, y + 2 + slot as i32),
+            format!("{}: {}", slot + 1, name),
+        );
+    });
+
+    draw_batch.submit(12000).expect("Error rendering inventory");
+
+    if let Some(key) = key {
+        if *key == VirtualKeyCode::Escape {
+            *turn_state = TurnState::AwaitingInput;
+        } else if let Some((item, _)) = key_to_slot(*key).and_then(|slot| items.get(slot)) {
+            if dropping {
+                commands.push((
+                    (),
+                    WantsToDrop {
+                        dropped_by: player,
+                        item: *item,
+                    },
+                ));
+                *turn_state = TurnState::PlayerTurn;
+            } else {
+                activate_or_aim(ecs, commands, turn_state, targeting, player, *item);
+            }
+        }
+    }
+}
@@ -4,26 +4,28 @@ use crate::prelude::*;
 #[read_component(Item)]
 #[read_component(ItemReceived)]
 #[read_component(AmuletOfYala)]
+#[read_component(Name)]
 pub fn item(
     message_entity: &Entity,
     item_received: &ItemReceived,
     ecs: &SubWorld,
     #[resource] turn_state: &mut TurnState,
+    #[resource] game_log: &mut GameLog,
     commands: &mut CommandBuffer,
 ) {
-    if ecs
-        .entry_ref(item_received.item)
-        .unwrap()
-        .get_component::<AmuletOfYala>()
-        .is_ok()
-    {
-        *turn_state = TurnState::Victory
+    let item = ecs.entry_ref(item_received.item).unwrap();
+    if let Ok(name) = item.get_component::<Name>() {
+        game_log.push(format!("You pick up the {}", name.0), CYAN);
     }
 
-    // maybe get power-ups and other stuff here!!!
-
-    //if maybe some items remain.....
-    commands.remove(item_received.item);
+    if item.get_component::<AmuletOfYala>().is_ok() {
+        *turn_state = TurnState::Victory;
+        commands.remove(item_received.item);
+    } else {
+        // the item leaves the map and goes into the receivers inventory
+        commands.remove_component::<Point>(item_received.item);
+        commands.add_component(item_received.item, Carried(item_received.receiver));
+    }
 
     commands.remove(*message_entity);
 }
@@ -0,0 +1,62 @@
+use crate::prelude::*;
+
+/// Maximum health gained with every level
+const HEALTH_PER_LEVEL: i32 = 5;
+/// Maximum mana gained with every level, by those who have any
+const MANA_PER_LEVEL: i32 = 2;
+
+/// Everyone with enough experience goes up a level, several at once after a big kill.
+/// Each level adds health and to hit, even levels add damage and odd levels armour
+#[system]
+#[read_component(Player)]
+#[read_component(Name)]
+#[write_component(Experience)]
+#[write_component(Health)]
+#[write_component(Attack)]
+#[write_component(Defense)]
+#[write_component(Mana)]
+pub fn levelling(ecs: &mut SubWorld, #[resource] game_log: &mut GameLog) {
+    <(
+        &mut Experience,
+        &mut Health,
+        &mut Attack,
+        &mut Defense,
+        Option<&mut Mana>,
+        Option<&Player>,
+        Option<&Name>,
+    )>::query()
+    .iter_mut(ecs)
+    .for_each(
+        |(experience, health, attack, defense, mut mana, player, name)| {
+            while experience.xp >= experience.next_level() {
+                experience.xp -= experience.next_level();
+                experience.level += 1;
+
+                health.max += HEALTH_PER_LEVEL;
+                health.current += HEALTH_PER_LEVEL;
+                if let Some(mana) = mana.as_mut() {
+                    mana.max += MANA_PER_LEVEL;
+                    mana.current += MANA_PER_LEVEL;
+                }
+                attack.to_hit += 1;
+                let gain = if experience.level % 2 == 0 {
+                    attack.damage.bonus += 1;
+                    "damage"
+                } else {
+                    defense.armour += 1;
+                    "armour"
+                };
+
+                if player.is_some() {
+                    game_log.push(format!("You reach level {}!", experience.level), GOLD);
+                    game_log.push(
+                        format!("+{} health, +1 to hit, +1 {}", HEALTH_PER_LEVEL, gain),
+                        GOLD,
+                    );
+                } else if let Some(name) = name {
+                    game_log.push(format!("The {} grows stronger", name.0), ORANGE);
+                }
+            }
+        },
+    );
+}
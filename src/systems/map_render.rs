@@ -1,19 +1,20 @@
 use crate::prelude::*;
 
 #[system]
+#[allow(clippy::borrowed_box)]
 #[read_component(Player)]
 #[read_component(FieldOfView)]
 pub fn map_render(
     ecs: &SubWorld,
     #[resource] map: &Map,
     #[resource] camera: &Camera,
-    #[resource] theme: &Box<dyn MapTheme>
+    #[resource] theme: &Box<dyn MapTheme>,
 ) {
     // get player field of view
     let player_fov = <&FieldOfView>::query()
         .filter(component::<Player>())
         .iter(ecs)
-        .nth(0)
+        .next()
         .unwrap();
 
     let mut draw_batch = DrawBatch::new();
@@ -1,16 +1,33 @@
 // this module is private to systems
+mod abilities;
 mod chasing;
 mod combat;
+mod drop_item;
 mod end_turn;
 mod entity_render;
+mod equipment;
+mod faction;
+mod fleeing;
+mod flow_fields;
 mod fov;
+mod history;
 mod hud;
+mod initiative;
+mod inventory;
 mod item;
+mod levelling;
 mod map_render;
+mod monster_ai;
 mod movement;
 mod player_input;
 mod random_move;
+mod searching;
+mod spatial_index;
+mod status_effects;
+mod targeting;
 mod tooltips;
+mod use_items;
+mod vision_boost;
 
 // prelude cannot include it since we made nothing public
 use crate::prelude::*;
@@ -20,6 +37,7 @@ use crate::prelude::*;
 /// While waiting for input, the map and entities still need to be rendered
 pub fn build_input_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
         .add_system(player_input::player_input_system())
         .add_system(fov::fov_system())
         .flush()
@@ -30,31 +48,81 @@ pub fn build_input_scheduler() -> Schedule {
         .build()
 }
 
+/// The inventory window is drawn on top of the map, no turn passes while it is open
+pub fn build_inventory_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(map_render::map_render_system())
+        .add_system(entity_render::entity_render_system())
+        .add_system(hud::hud_system())
+        .add_system(inventory::inventory_system())
+        .build()
+}
+
+/// The full screen message history replaces the map until it is closed
+pub fn build_history_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(history::history_system())
+        .build()
+}
+
+/// Picking a target keeps the map on screen, nothing moves until the player fires or gives up
+pub fn build_targeting_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(map_render::map_render_system())
+        .add_system(entity_render::entity_render_system())
+        .add_system(hud::hud_system())
+        .add_system(targeting::targeting_system())
+        .add_system(targeting::targeting_render_system())
+        .build()
+}
+
 /// If a player is moving we want to detect collisions, flush and render them, and finally check end turn state
 pub fn build_player_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(abilities::casting_system())
+        .flush()
+        .add_system(use_items::use_items_system())
+        .add_system(drop_item::drop_item_system())
+        .flush()
         .add_system(combat::combat_system())
+        .add_system(levelling::levelling_system())
         .flush()
         .add_system(movement::movement_system())
         .flush()
         .add_system(fov::fov_system())
         .flush()
         .add_system(item::item_system())
+        .add_system(vision_boost::vision_boost_system())
         .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
+        .add_system(status_effects::status_effects_system())
+        .add_system(abilities::cooldowns_system())
+        .add_system(initiative::initiative_system())
         .add_system(end_turn::end_turn_system())
         .build()
 }
 
-/// monsters have the random move system, check collisions and also end their turns
+/// monsters with enough energy saved up move, attack and end their turns, the initiative
+/// system keeps running this schedule for as long as monsters are ready to act
 pub fn build_monster_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(flow_fields::flow_fields_system())
+        .add_system(monster_ai::monster_ai_system())
         .add_system(random_move::random_move_system())
         .add_system(chasing::chasing_system())
+        .add_system(searching::searching_system())
+        .add_system(fleeing::fleeing_system())
+        .flush()
+        .add_system(abilities::casting_system())
         .flush()
+        .add_system(use_items::use_items_system())
         .add_system(combat::combat_system())
+        .add_system(levelling::levelling_system())
         .flush()
         .add_system(movement::movement_system())
         .add_system(fov::fov_system())
@@ -64,6 +132,9 @@ pub fn build_monster_scheduler() -> Schedule {
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
+        .add_system(status_effects::status_effects_system())
+        .add_system(abilities::cooldowns_system())
+        .add_system(initiative::initiative_system())
         .add_system(end_turn::end_turn_system())
         .build()
 }
@@ -0,0 +1,131 @@
+use super::faction::reaction;
+use crate::prelude::*;
+
+/// Monsters at or below a quarter of their health run for it
+const FLEE_DIVISOR: i32 = 4;
+/// How many of its actions a monster spends looking for an enemy it lost sight of
+const SEARCH_TURNS: i32 = 10;
+
+/// Moves every monster that is about to act into its next state, based on which of its
+/// enemies it can see, how badly it is hurt and whether it is afraid
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Name)]
+#[read_component(Health)]
+#[read_component(FieldOfView)]
+#[read_component(Energy)]
+#[read_component(ChasingPlayer)]
+#[read_component(Faction)]
+#[read_component(Charmed)]
+#[read_component(Provoked)]
+#[read_component(Asleep)]
+#[read_component(Afraid)]
+#[write_component(AiState)]
+pub fn monster_ai(
+    ecs: &mut SubWorld,
+    #[resource] index: &SpatialIndex,
+    #[resource] game_log: &mut GameLog,
+) {
+    let player = <Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .copied()
+        .expect("Could not find player");
+
+    let mut transitions = Vec::new();
+    <(Entity, &Point, &AiState, &FieldOfView, &Health, &Energy)>::query()
+        .filter(component::<ChasingPlayer>() & !component::<Asleep>())
+        .iter(ecs)
+        .filter(|(_, _, _, _, _, energy)| energy.is_ready())
+        .for_each(|(monster, pos, state, fov, health, _)| {
+            // the closest creature in view this monster wants dead
+            let target = fov
+                .visible_tiles
+                .iter()
+                .flat_map(|tile| index.entities_at(*tile).iter().map(move |e| (*e, *tile)))
+                .filter(|(other, _)| {
+                    ecs.entry_ref(*other)
+                        .is_ok_and(|entry| entry.get_component::<Health>().is_ok())
+                        && reaction(ecs, *monster, *other) == Reaction::Hostile
+                })
+                .min_by_key(|(_, tile)| {
+                    // ties are broken by position, keeping the game the same for every seed
+                    let distance = DistanceAlg::Manhattan.distance2d(*pos, *tile) as i32;
+                    (distance, tile.y, tile.x)
+                });
+
+            let wounded = health.current * FLEE_DIVISOR <= health.max;
+            let afraid = ecs
+                .entry_ref(*monster)
+                .is_ok_and(|entry| entry.get_component::<Afraid>().is_ok());
+
+            // the flee field leads away from the player, other enemies are fought to the end
+            let next = match (*state, target) {
+                (_, Some((target, _))) if target == player && (wounded || afraid) => {
+                    AiState::Fleeing
+                }
+                (_, Some((target, last_seen))) => AiState::Hunting { target, last_seen },
+                (AiState::Hunting { last_seen, .. }, None) => AiState::Searching {
+                    target: last_seen,
+                    turns: SEARCH_TURNS,
+                },
+                // out of sight is good enough, the monster calms down and roams again
+                (AiState::Fleeing, None) => AiState::Wandering,
+                (other, None) => other,
+            };
+
+            if next != *state {
+                transitions.push((*monster, next));
+            }
+        });
+
+    transitions.into_iter().for_each(|(monster, next)| {
+        if let Ok(mut entry) = ecs.entry_mut(monster) {
+            if next == AiState::Fleeing {
+                if let Ok(name) = entry.get_component::<Name>() {
+                    game_log.push(format!("The {} turns to flee", name.0), GRAY);
+                }
+            }
+            if let Ok(state) = entry.get_component_mut::<AiState>() {
+                *state = next;
+            }
+        }
+    });
+}
+
+/// Shared by all monster behaviours: attack whatever hostile creature stands at `dest`,
+/// otherwise step there unless someone is in the way, then we wait for them to move.
+/// Systems calling this need read access to `Health`, `Faction`, `Charmed` and `Provoked`
+pub fn step_or_attack(
+    ecs: &SubWorld,
+    index: &SpatialIndex,
+    commands: &mut CommandBuffer,
+    monster: Entity,
+    dest: Point,
+) {
+    let victim = index.entities_at(dest).iter().find(|entity| {
+        ecs.entry_ref(**entity)
+            .is_ok_and(|entry| entry.get_component::<Health>().is_ok())
+            && reaction(ecs, monster, **entity) == Reaction::Hostile
+    });
+
+    if let Some(victim) = victim {
+        commands.push((
+            (),
+            WantsToAttack {
+                victim: *victim,
+                attacker: monster,
+            },
+        ));
+    } else if !index.is_blocked(dest) {
+        commands.push((
+            (),
+            WantsToMove {
+                entity: monster,
+                destination: dest,
+            },
+        ));
+    }
+}
@@ -1,24 +1,84 @@
+use super::combat::apply_damage;
+use super::status_effects::inflict;
 use crate::prelude::*;
 
+/// what stepping into lava costs anyone not flying over it
+const LAVA_DAMAGE: i32 = 4;
+
 /// The movement system iterates all entities with a WantsToMove component.
 /// It then checks that the move is valid, and if it is replaces the Point
 /// component of the target entity. If the entity is a player, it also updates the camera.”
 // derive the query parameters from the arguments (only for single queries
 #[system(for_each)]
+#[allow(clippy::too_many_arguments)]
 // these provide entities containing components to the SubWorld
 #[read_component(Player)]
 #[read_component(FieldOfView)]
+#[read_component(Point)]
+#[read_component(BlocksTile)]
+#[read_component(Confused)]
+#[read_component(Trap)]
+#[read_component(InflictsStatus)]
+#[read_component(Name)]
+#[read_component(Poisoned)]
+#[read_component(Asleep)]
+#[read_component(Afraid)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+#[read_component(Locomotion)]
+#[read_component(ExperienceValue)]
+#[write_component(Health)]
+#[write_component(Experience)]
 pub fn movement(
     message_entity: &Entity,
     movement_intention: &WantsToMove,
     #[resource] map: &mut Map,
     #[resource] camera: &mut Camera,
+    #[resource] index: &mut SpatialIndex,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] game_log: &mut GameLog,
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
 ) {
-    if map.can_enter_tile(movement_intention.destination) {
+    let movement_intention = &WantsToMove {
+        destination: confused_step(ecs, map, rng, movement_intention),
+        ..*movement_intention
+    };
+    let (is_player, locomotion, pos) = match ecs.entry_ref(movement_intention.entity) {
+        Ok(entry) => (
+            entry.get_component::<Player>().is_ok(),
+            entry.get_component::<Locomotion>().ok().copied(),
+            entry.get_component::<Point>().ok().copied(),
+        ),
+        Err(_) => (false, None, None),
+    };
+    // a diagonal step that cuts a corner it may not, teleports and the like go anywhere
+    let cuts_corner = pos.is_some_and(|pos| {
+        DistanceAlg::Pythagoras.distance2d(pos, movement_intention.destination) < 1.5
+            && !map.is_step(pos, movement_intention.destination)
+    });
+    let mut moved = false;
+
+    // bumping into a closed door opens it, which takes the whole move
+    if map.is_closed_door(movement_intention.destination) {
+        open_door(ecs, commands, map, game_log, movement_intention);
+    // two monsters heading for the same tile, the first one gets it
+    } else if map.can_cross(movement_intention.destination, locomotion)
+        && !index.is_blocked(movement_intention.destination)
+        && !cuts_corner
+    {
         if let Ok(entry) = ecs.entry_ref(movement_intention.entity) {
             if let Ok(fov) = entry.get_component::<FieldOfView>() {
+                // keep the index current for the moves processed after this one
+                if let Ok(pos) = entry.get_component::<Point>() {
+                    index.move_entity(
+                        movement_intention.entity,
+                        *pos,
+                        movement_intention.destination,
+                        entry.get_component::<BlocksTile>().is_ok(),
+                    );
+                }
+
                 // since we moved, clone a dirty new fov, for fov system to rebuild it
                 commands.add_component(movement_intention.entity, fov.clone_dirty());
 
@@ -40,10 +100,198 @@ pub fn movement(
                         map.revealed_tiles[map_idx(pos.x, pos.y)] = true;
                     })
                 }
+
+                spring_traps(ecs, commands, index, game_log, movement_intention);
+                moved = true;
             }
         }
+    } else if is_player
+        && map.can_enter_tile(movement_intention.destination)
+        && !map.can_cross(movement_intention.destination, locomotion)
+    {
+        game_log.push("The water is too deep to wade into", GRAY);
+    }
+
+    let burns = map
+        .try_idx(movement_intention.destination)
+        .is_some_and(|idx| map.tiles[idx] == TileType::Lava);
+    if moved && burns && locomotion != Some(Locomotion::Flying) {
+        burn(ecs, commands, index, game_log, movement_intention);
     }
 
     // mark the message/entity as processed so we don't do it again
     commands.remove(*message_entity);
 }
+
+/// Confused entities stumble into a random neighbouring tile instead of where they meant
+/// to go, teleports and other long distance moves are left alone
+fn confused_step(
+    ecs: &SubWorld,
+    map: &Map,
+    rng: &mut RandomNumberGenerator,
+    movement_intention: &WantsToMove,
+) -> Point {
+    let pos = ecs
+        .entry_ref(movement_intention.entity)
+        .ok()
+        .and_then(|entry| {
+            if entry.get_component::<Confused>().is_ok() {
+                entry.get_component::<Point>().ok().copied()
+            } else {
+                None
+            }
+        });
+
+    match pos {
+        Some(pos)
+            if DistanceAlg::Pythagoras.distance2d(pos, movement_intention.destination) < 1.5 =>
+        {
+            pos + *rng
+                .random_slice_entry(map.step_deltas())
+                .unwrap_or(&Point::zero())
+        }
+        _ => movement_intention.destination,
+    }
+}
+
+/// The door swings open and the mover stays put. Anyone who could see the door gets their
+/// field of view rebuilt, since they may now see through it
+fn open_door(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    map: &mut Map,
+    game_log: &mut GameLog,
+    movement_intention: &WantsToMove,
+) {
+    let door = movement_intention.destination;
+    map.set_tile(door, TileType::DoorOpen);
+
+    let mut player_sees = false;
+    <(Entity, &FieldOfView, Option<&Player>)>::query()
+        .iter(ecs)
+        .filter(|(_, fov, _)| fov.visible_tiles.contains(&door))
+        .for_each(|(entity, fov, player)| {
+            player_sees |= player.is_some();
+            commands.add_component(*entity, fov.clone_dirty());
+        });
+
+    if let Ok(entry) = ecs.entry_ref(movement_intention.entity) {
+        if entry.get_component::<Player>().is_ok() {
+            game_log.push("You open the door", GRAY);
+        } else if let (true, Ok(name)) = (player_sees, entry.get_component::<Name>()) {
+            game_log.push(format!("The {} opens a door", name.0), GRAY);
+        }
+    }
+}
+
+/// Lava burns whoever steps into it, monsters may not make it out again
+fn burn(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    index: &mut SpatialIndex,
+    game_log: &mut GameLog,
+    movement_intention: &WantsToMove,
+) {
+    let destination = movement_intention.destination;
+    let player_sees = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .is_some_and(|fov| fov.visible_tiles.contains(&destination));
+
+    let (is_player, name, blocks) = match ecs.entry_ref(movement_intention.entity) {
+        Ok(entry) => (
+            entry.get_component::<Player>().is_ok(),
+            entry
+                .get_component::<Name>()
+                .map(|name| name.0.clone())
+                .unwrap_or_default(),
+            entry.get_component::<BlocksTile>().is_ok(),
+        ),
+        Err(_) => return,
+    };
+
+    // the end turn system takes care of a burnt player
+    let killed = apply_damage(
+        ecs,
+        commands,
+        index,
+        None,
+        movement_intention.entity,
+        LAVA_DAMAGE,
+    );
+    if is_player {
+        game_log.push(format!("The lava burns you for {}", LAVA_DAMAGE), ORANGE);
+    } else if killed {
+        // the index already has it at the destination, its position only changes once
+        // the commands are flushed
+        index.remove(movement_intention.entity, destination, blocks);
+        if player_sees {
+            game_log.push(format!("The {} burns to death in the lava", name), ORANGE);
+        }
+    }
+}
+
+/// Every trap at the destination goes off on whoever just stepped there, and is used up
+fn spring_traps(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    index: &mut SpatialIndex,
+    game_log: &mut GameLog,
+    movement_intention: &WantsToMove,
+) {
+    let destination = movement_intention.destination;
+    let traps: Vec<(Entity, String, InflictsStatus)> = index
+        .entities_at(destination)
+        .iter()
+        .filter_map(|entity| {
+            let entry = ecs.entry_ref(*entity).ok()?;
+            entry.get_component::<Trap>().ok()?;
+            let name = entry.get_component::<Name>().ok()?.0.clone();
+            Some((
+                *entity,
+                name,
+                *entry.get_component::<InflictsStatus>().ok()?,
+            ))
+        })
+        .collect();
+    if traps.is_empty() {
+        return;
+    }
+
+    let player_sees = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .is_some_and(|fov| fov.visible_tiles.contains(&destination));
+    let victim = ecs.entry_ref(movement_intention.entity).ok();
+    let is_player = victim
+        .as_ref()
+        .is_some_and(|entry| entry.get_component::<Player>().is_ok());
+    let victim_name = victim.and_then(|entry| entry.get_component::<Name>().ok().cloned());
+    let seen = is_player || player_sees;
+
+    traps.into_iter().for_each(|(trap, name, inflicts)| {
+        if seen {
+            let text = match (is_player, &victim_name) {
+                (true, _) => format!("You step on a {}", name),
+                (false, Some(victim_name)) => {
+                    format!("The {} steps on a {}", victim_name.0, name)
+                }
+                (false, None) => format!("A {} goes off", name),
+            };
+            game_log.push(text, ORANGE);
+        }
+
+        inflict(
+            ecs,
+            commands,
+            game_log,
+            movement_intention.entity,
+            inflicts.effect,
+            seen,
+        );
+        commands.remove(trap);
+        index.remove(trap, destination, false);
+    });
+}
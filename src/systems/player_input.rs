@@ -1,25 +1,71 @@
+use super::abilities::{cast_or_aim, key_to_ability, known_abilities};
+use super::equipment::equipped_in;
+use super::faction::reaction;
+use super::inventory::{carried_items, key_to_slot, MAX_CARRIED};
+use super::targeting::activate_or_aim;
 use crate::prelude::*;
 
 #[system]
+#[allow(clippy::too_many_arguments)]
 #[write_component(Health)]
 #[read_component(Point)]
-#[read_component(Enemy)]
+#[read_component(Faction)]
+#[read_component(Charmed)]
+#[read_component(Provoked)]
 #[read_component(Item)]
+#[read_component(AmuletOfYala)]
+#[read_component(Carried)]
+#[read_component(Name)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(AttackBonus)]
+#[read_component(DefenseBonus)]
+#[read_component(Asleep)]
+#[read_component(Ranged)]
+#[read_component(AreaOfEffect)]
+#[read_component(Ability)]
+#[read_component(Mana)]
 #[read_component(Player)] // request read access to the player marker type
 pub fn player_input(
     ecs: &mut SubWorld, // only contains the requested components
     commands: &mut CommandBuffer,
     #[resource] key: &Option<VirtualKeyCode>, // TODO: how to do multiple keys?
     #[resource] turn_state: &mut TurnState,
+    #[resource] targeting: &mut Option<Targeting>,
+    #[resource] game_log: &mut GameLog,
+    #[resource] index: &SpatialIndex,
+    #[resource] map: &Map,
 ) {
     if let Some(key) = key {
-        let delta = match key {
-            VirtualKeyCode::Up => Point::new(0, -1),
-            VirtualKeyCode::Down => Point::new(0, 1),
-            VirtualKeyCode::Left => Point::new(-1, 0),
-            VirtualKeyCode::Right => Point::new(1, 0),
-            _ => Point::zero(),
-        };
+        // opening the inventory does not cost a turn
+        match key {
+            VirtualKeyCode::I => {
+                *turn_state = TurnState::ShowingInventory;
+                return;
+            }
+            VirtualKeyCode::D => {
+                *turn_state = TurnState::DroppingItem;
+                return;
+            }
+            VirtualKeyCode::V => {
+                *turn_state = TurnState::ShowingHistory;
+                return;
+            }
+            _ => {}
+        }
+
+        // any key lets a sleeping player snooze on until the status effects wake them
+        let asleep = <&Asleep>::query()
+            .filter(component::<Player>())
+            .iter(ecs)
+            .next()
+            .is_some();
+        if asleep {
+            *turn_state = TurnState::PlayerTurn;
+            return;
+        }
+
+        let delta = key_to_step(*key);
 
         let mut did_something = false;
 
@@ -30,22 +76,34 @@ pub fn player_input(
             // filter requires the component to exists, but can't use its content
             .filter(component::<Player>());
 
-        let (player_entity, destination) = players
+        let (player_entity, player_pos) = players
             .iter(ecs)
-            .find_map(|(entity, pos)| Some((*entity, *pos + delta)))
+            .map(|(entity, pos)| (*entity, *pos))
+            .next()
             .unwrap();
+        let destination = player_pos + delta;
 
-        if delta.x != 0 || delta.y != 0 {
-            // now get monsters to see if player will move into one
-            let mut enemies = <(Entity, &Point)>::query().filter(component::<Enemy>());
+        // diagonals are only there with eight-way movement, and not past every corner
+        if delta != Point::zero() && !map.is_step(player_pos, destination) {
+            return;
+        }
 
+        if delta.x != 0 || delta.y != 0 {
             let mut hit_enemy = false;
-            // look for any monsters we might have moved into
-            enemies
-                .iter_mut(ecs)
-                .filter(|(_, pos)| **pos == destination)
-                .for_each(|(enemy, _)| {
+            // look for any creature we might have moved into, allies are left alone
+            index
+                .entities_at(destination)
+                .iter()
+                .filter(|entity| {
+                    ecs.entry_ref(**entity)
+                        .is_ok_and(|entry| entry.get_component::<Health>().is_ok())
+                })
+                .for_each(|creature| {
                     hit_enemy = true;
+
+                    if reaction(ecs, player_entity, *creature) == Reaction::Friendly {
+                        return;
+                    }
                     did_something = true;
 
                     // we would move into a monster now
@@ -53,25 +111,7 @@ pub fn player_input(
                         (),
                         WantsToAttack {
                             attacker: player_entity,
-                            victim: *enemy,
-                        },
-                    ));
-                });
-
-            let mut items = <(Entity, &Point)>::query().filter(component::<Item>());
-
-            // check if we hit an item, apply item effects, or end game
-            items
-                .iter(ecs)
-                .filter(|(_, pos)| **pos == destination)
-                .for_each(|(item, _)| {
-                    did_something = true;
-
-                    commands.push((
-                        (),
-                        ItemReceived {
-                            receiver: player_entity,
-                            item: *item,
+                            victim: *creature,
                         },
                     ));
                 });
@@ -87,11 +127,87 @@ pub fn player_input(
                     },
                 ));
             }
+        } else if *key == VirtualKeyCode::G {
+            // pick up everything lying where the player stands that still fits in the pack,
+            // the amulet always does
+            let mut room = MAX_CARRIED.saturating_sub(carried_items(ecs, player_entity).len());
+            let mut left_behind = false;
+            index
+                .entities_at(player_pos)
+                .iter()
+                .filter_map(|entity| ecs.entry_ref(*entity).ok().map(|entry| (*entity, entry)))
+                .filter(|(_, entry)| entry.get_component::<Item>().is_ok())
+                .for_each(|(item, entry)| {
+                    if entry.get_component::<AmuletOfYala>().is_err() {
+                        if room == 0 {
+                            left_behind = true;
+                            return;
+                        }
+                        room -= 1;
+                    }
+                    did_something = true;
+
+                    commands.push((
+                        (),
+                        ItemReceived {
+                            receiver: player_entity,
+                            item,
+                        },
+                    ));
+                });
+
+            if left_behind {
+                game_log.push("Your pack is full", GRAY);
+                // nothing picked up, so no turn spent either
+                if !did_something {
+                    return;
+                }
+            }
+        } else if *key == VirtualKeyCode::F {
+            // taking aim costs nothing, only the shot ends the turn
+            let weapon =
+                equipped_in(ecs, player_entity, EquipmentSlot::Weapon).and_then(|weapon| {
+                    let range = ecs
+                        .entry_ref(weapon)
+                        .ok()?
+                        .get_component::<Ranged>()
+                        .ok()?
+                        .range;
+                    Some((weapon, range))
+                });
+            match weapon {
+                Some((weapon, range)) => {
+                    *targeting = Some(Targeting::new(Aim::Weapon(weapon), range, 0));
+                    *turn_state = TurnState::Targeting;
+                }
+                None => game_log.push("You have nothing to shoot with", GRAY),
+            }
+            return;
+        } else if let Some(slot) = key_to_slot(*key) {
+            // number keys use an item straight from the inventory, or take aim with it
+            if let Some((item, _)) = carried_items(ecs, player_entity).get(slot) {
+                activate_or_aim(ecs, commands, turn_state, targeting, player_entity, *item);
+                return;
+            }
+        } else if let Some(slot) = key_to_ability(*key) {
+            // function keys cast an ability, or take aim with it
+            if let Some((ability, _)) = known_abilities(ecs, player_entity).get(slot) {
+                cast_or_aim(
+                    ecs,
+                    commands,
+                    turn_state,
+                    targeting,
+                    game_log,
+                    player_entity,
+                    *ability,
+                );
+            }
+            return;
         }
 
         if !did_something {
             // lets give the player some health for waiting a turn
-            if let Ok(mut health) = ecs
+            if let Ok(health) = ecs
                 .entry_mut(player_entity)
                 .unwrap()
                 .get_component_mut::<Health>()
@@ -104,3 +220,19 @@ pub fn player_input(
         *turn_state = TurnState::PlayerTurn;
     }
 }
+
+/// The arrow keys move straight, the numpad and the vi-keys h, j, k, l, y, u, b and n
+/// diagonally as well
+fn key_to_step(key: VirtualKeyCode) -> Point {
+    match key {
+        VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => Point::new(0, -1),
+        VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => Point::new(0, 1),
+        VirtualKeyCode::Left | VirtualKeyCode::Numpad4 | VirtualKeyCode::H => Point::new(-1, 0),
+        VirtualKeyCode::Right | VirtualKeyCode::Numpad6 | VirtualKeyCode::L => Point::new(1, 0),
+        VirtualKeyCode::Numpad7 | VirtualKeyCode::Y => Point::new(-1, -1),
+        VirtualKeyCode::Numpad9 | VirtualKeyCode::U => Point::new(1, -1),
+        VirtualKeyCode::Numpad1 | VirtualKeyCode::B => Point::new(-1, 1),
+        VirtualKeyCode::Numpad3 | VirtualKeyCode::N => Point::new(1, 1),
+        _ => Point::zero(),
+    }
+}
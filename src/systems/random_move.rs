@@ -1,66 +1,45 @@
+use super::monster_ai::step_or_attack;
 use crate::prelude::*;
 
+/// Moves monsters that always stumble about at random, and wandering ones
 #[system]
 #[read_component(Point)]
-#[read_component(Health)]
 #[read_component(Player)]
-#[read_component(Name)]
 #[read_component(MovesRandomly)]
-pub fn random_move(ecs: &SubWorld, commands: &mut CommandBuffer) {
-    let mut movers = <(Entity, &Point, &MovesRandomly)>::query();
-    let mut positions = <(Entity, &Point, &Health)>::query();
-
-    movers.iter(ecs).for_each(|(monster, pos, _)| {
-        let mut rng = RandomNumberGenerator::new();
-        let dest = *pos
-            + match rng.range(0, 4) {
-                0 => Point::new(-1, 0),
-                1 => Point::new(1, 0),
-                2 => Point::new(0, -1),
-                _ => Point::new(0, 1),
-            };
-
-        let mut attacked = false;
-        positions
-            .iter(ecs)
-            .filter(|(_, pos, _)| **pos == dest)
-            .for_each(|(victim, _, _)| {
-                // we only want to attack the player for now
-                if ecs
-                    .entry_ref(*victim)
-                    .unwrap()
-                    .get_component::<Player>()
-                    .is_ok()
-                {
-                    attacked = true;
-
-                    println!(
-                        "monster {} attacks player!",
-                        &ecs.entry_ref(*monster)
-                            .unwrap()
-                            .get_component::<Name>()
-                            .unwrap()
-                            .0
-                    );
+#[read_component(AiState)]
+#[read_component(Energy)]
+#[read_component(Health)]
+#[read_component(Faction)]
+#[read_component(Charmed)]
+#[read_component(Provoked)]
+#[read_component(Asleep)]
+pub fn random_move(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] index: &SpatialIndex,
+    #[resource] map: &Map,
+) {
+    let mut movers = <(
+        Entity,
+        &Point,
+        &Energy,
+        Option<&MovesRandomly>,
+        Option<&AiState>,
+    )>::query()
+    .filter(!component::<Asleep>());
 
-                    commands.push((
-                        (),
-                        WantsToAttack {
-                            victim: *victim,
-                            attacker: *monster,
-                        },
-                    ));
-                }
-            });
+    movers
+        .iter(ecs)
+        .filter(|(_, _, energy, random, state)| {
+            energy.is_ready() && (random.is_some() || state == &Some(&AiState::Wandering))
+        })
+        .for_each(|(monster, pos, _, _, _)| {
+            let dest = *pos
+                + *rng
+                    .random_slice_entry(map.step_deltas())
+                    .unwrap_or(&Point::zero());
 
-        if !attacked {
-            commands.push((
-                (),
-                WantsToMove {
-                    entity: *monster,
-                    destination: dest,
-                },
-            ));
-        }
-    });
+            step_or_attack(ecs, index, commands, *monster, dest);
+        });
 }
@@ -0,0 +1,53 @@
+use super::monster_ai::step_or_attack;
+use crate::prelude::*;
+
+/// Monsters that lost sight of the player walk to where they saw them last, giving up
+/// once they get there or run out of patience
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Energy)]
+#[read_component(Health)]
+#[read_component(Faction)]
+#[read_component(Charmed)]
+#[read_component(Provoked)]
+#[read_component(Asleep)]
+#[write_component(AiState)]
+pub fn searching(
+    ecs: &mut SubWorld,
+    #[resource] map: &Map,
+    #[resource] index: &SpatialIndex,
+    commands: &mut CommandBuffer,
+) {
+    let mut steps = Vec::new();
+
+    <(Entity, &Point, &mut AiState, &Energy)>::query()
+        .filter(!component::<Asleep>())
+        .iter_mut(ecs)
+        .filter(|(_, _, _, energy)| energy.is_ready())
+        .for_each(|(monster, pos, state, _)| {
+            if let AiState::Searching { target, turns } = *state {
+                if *pos == target || turns < 1 {
+                    *state = AiState::Wandering;
+                    return;
+                }
+
+                *state = AiState::Searching {
+                    target,
+                    turns: turns - 1,
+                };
+
+                let path = a_star_search(map_idx(pos.x, pos.y), map_idx(target.x, target.y), map);
+                if path.success && path.steps.len() > 1 {
+                    steps.push((*monster, map.index_to_point2d(path.steps[1])));
+                } else {
+                    // nowhere to go, no point in looking any further
+                    *state = AiState::Wandering;
+                }
+            }
+        });
+
+    steps
+        .into_iter()
+        .for_each(|(monster, dest)| step_or_attack(ecs, index, commands, monster, dest));
+}
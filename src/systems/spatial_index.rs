@@ -0,0 +1,14 @@
+use crate::prelude::*;
+
+/// Records where every positioned entity stands, items carried by someone have no
+/// position and are left out
+#[system]
+#[read_component(Point)]
+#[read_component(BlocksTile)]
+pub fn spatial_index(ecs: &SubWorld, #[resource] index: &mut SpatialIndex) {
+    index.clear();
+
+    <(Entity, &Point, Option<&BlocksTile>)>::query()
+        .iter(ecs)
+        .for_each(|(entity, pos, blocks)| index.add(*entity, *pos, blocks.is_some()));
+}
@@ -0,0 +1,260 @@
+use super::initiative::acting_entities;
+use crate::prelude::*;
+use legion::world::EntryRef;
+
+/// Counts down every timed effect of the entities about to pay for their action, effects
+/// last for a number of the affected entity's own actions. Poison hurts with each of them
+#[system]
+#[read_component(Player)]
+#[read_component(Name)]
+#[read_component(Energy)]
+#[read_component(Point)]
+#[read_component(BlocksTile)]
+#[read_component(FieldOfView)]
+#[write_component(Health)]
+#[write_component(Hasted)]
+#[write_component(Slowed)]
+#[write_component(Charmed)]
+#[write_component(Poisoned)]
+#[write_component(Confused)]
+#[write_component(Asleep)]
+#[write_component(Afraid)]
+pub fn status_effects(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] turn_state: &TurnState,
+    #[resource] game_log: &mut GameLog,
+    #[resource] index: &mut SpatialIndex,
+) {
+    let player_fov = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .cloned();
+
+    acting_entities(ecs, turn_state)
+        .into_iter()
+        .for_each(|entity| {
+            let mut entry = match ecs.entry_mut(entity) {
+                Ok(entry) => entry,
+                Err(_) => return,
+            };
+            let is_player = entry.get_component::<Player>().is_ok();
+            let name = entry
+                .get_component::<Name>()
+                .map(|name| name.0.clone())
+                .unwrap_or_default();
+            let pos = entry.get_component::<Point>().ok().copied();
+            let seen = pos.is_some_and(|pos| {
+                player_fov
+                    .as_ref()
+                    .is_some_and(|fov| fov.visible_tiles.contains(&pos))
+            });
+
+            if let Ok(hasted) = entry.get_component_mut::<Hasted>() {
+                if tick(&mut hasted.turns) {
+                    commands.remove_component::<Hasted>(entity);
+                    if is_player {
+                        game_log.push("You slow down to your normal pace", GRAY);
+                    }
+                }
+            }
+
+            if let Ok(slowed) = entry.get_component_mut::<Slowed>() {
+                if tick(&mut slowed.turns) {
+                    commands.remove_component::<Slowed>(entity);
+                    if is_player {
+                        game_log.push("You are no longer sluggish", GRAY);
+                    }
+                }
+            }
+
+            if let Ok(confused) = entry.get_component_mut::<Confused>() {
+                if tick(&mut confused.turns) {
+                    commands.remove_component::<Confused>(entity);
+                    if is_player {
+                        game_log.push("Your head clears", GRAY);
+                    }
+                }
+            }
+
+            if let Ok(asleep) = entry.get_component_mut::<Asleep>() {
+                if tick(&mut asleep.turns) {
+                    commands.remove_component::<Asleep>(entity);
+                    if is_player {
+                        game_log.push("You wake up", GRAY);
+                    } else if seen {
+                        game_log.push(format!("The {} wakes up", name), GRAY);
+                    }
+                }
+            }
+
+            if let Ok(afraid) = entry.get_component_mut::<Afraid>() {
+                if tick(&mut afraid.turns) {
+                    commands.remove_component::<Afraid>(entity);
+                    if is_player {
+                        game_log.push("You regain your courage", GRAY);
+                    } else if seen {
+                        game_log.push(format!("The {} regains its courage", name), GRAY);
+                    }
+                }
+            }
+
+            if let Ok(charmed) = entry.get_component_mut::<Charmed>() {
+                if tick(&mut charmed.turns) {
+                    commands.remove_component::<Charmed>(entity);
+                    if !name.is_empty() {
+                        game_log.push(format!("The {} is no longer charmed", name), PINK);
+                    }
+                }
+            }
+
+            let (poison_damage, poison_ran_out) = match entry.get_component_mut::<Poisoned>() {
+                Ok(poisoned) => (poisoned.damage, tick(&mut poisoned.turns)),
+                Err(_) => return,
+            };
+            if poison_ran_out {
+                commands.remove_component::<Poisoned>(entity);
+            }
+
+            if let Ok(health) = entry.get_component_mut::<Health>() {
+                health.current -= poison_damage;
+                if is_player {
+                    game_log.push(format!("You take {} poison damage", poison_damage), PURPLE);
+                    if poison_ran_out {
+                        game_log.push("The poison wears off", GRAY);
+                    }
+                } else if health.current < 1 {
+                    // the end turn system takes care of a poisoned player
+                    commands.remove(entity);
+                    if let Some(pos) = pos {
+                        index.remove(entity, pos, entry.get_component::<BlocksTile>().is_ok());
+                    }
+                    if seen {
+                        game_log.push(format!("The {} dies of poison", name), PURPLE);
+                    }
+                }
+            }
+        });
+}
+
+/// Counts off one action, true once the effect has run out
+fn tick(turns: &mut i32) -> bool {
+    *turns -= 1;
+    *turns < 1
+}
+
+/// Starts `effect` on `target`, a longer or stronger dose replaces one already running.
+/// Systems calling this need read access to `Player`, `Name`, `Poisoned`, `Confused`,
+/// `Asleep`, `Hasted`, `Slowed` and `Afraid`
+pub fn inflict(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    game_log: &mut GameLog,
+    target: Entity,
+    effect: StatusEffect,
+    seen: bool,
+) {
+    let entry = match ecs.entry_ref(target) {
+        Ok(entry) => entry,
+        Err(_) => return,
+    };
+
+    let (you, them) = match effect {
+        StatusEffect::Poison { turns, damage } => {
+            let current = entry.get_component::<Poisoned>().ok();
+            commands.add_component(
+                target,
+                Poisoned {
+                    turns: current.map_or(turns, |p| p.turns.max(turns)),
+                    damage: current.map_or(damage, |p| p.damage.max(damage)),
+                },
+            );
+            ("You are poisoned", "is poisoned")
+        }
+        StatusEffect::Confusion(turns) => {
+            let current = entry.get_component::<Confused>().map_or(0, |c| c.turns);
+            commands.add_component(
+                target,
+                Confused {
+                    turns: current.max(turns),
+                },
+            );
+            ("You feel confused", "looks confused")
+        }
+        StatusEffect::Sleep(turns) => {
+            let current = entry.get_component::<Asleep>().map_or(0, |a| a.turns);
+            commands.add_component(
+                target,
+                Asleep {
+                    turns: current.max(turns),
+                },
+            );
+            ("You fall asleep", "falls asleep")
+        }
+        StatusEffect::Haste(turns) => {
+            let current = entry.get_component::<Hasted>().map_or(0, |h| h.turns);
+            commands.add_component(
+                target,
+                Hasted {
+                    turns: current.max(turns),
+                },
+            );
+            ("You feel yourself speed up", "speeds up")
+        }
+        StatusEffect::Slow(turns) => {
+            let current = entry.get_component::<Slowed>().map_or(0, |s| s.turns);
+            commands.add_component(
+                target,
+                Slowed {
+                    turns: current.max(turns),
+                },
+            );
+            ("You feel sluggish", "slows down")
+        }
+        StatusEffect::Fear(turns) => {
+            let current = entry.get_component::<Afraid>().map_or(0, |a| a.turns);
+            commands.add_component(
+                target,
+                Afraid {
+                    turns: current.max(turns),
+                },
+            );
+            ("You are terrified", "is terrified")
+        }
+    };
+
+    if entry.get_component::<Player>().is_ok() {
+        game_log.push(you, PURPLE);
+    } else if seen {
+        if let Ok(name) = entry.get_component::<Name>() {
+            game_log.push(format!("The {} {}", name.0, them), PURPLE);
+        }
+    }
+}
+
+/// The timed effects on an entity with how many actions they have left, for display.
+/// Systems calling this need read access to `Hasted`, `Slowed`, `Poisoned`, `Confused`,
+/// `Asleep` and `Afraid`
+pub fn active_effects(entry: &EntryRef) -> Vec<(&'static str, i32)> {
+    let mut effects = Vec::new();
+    if let Ok(hasted) = entry.get_component::<Hasted>() {
+        effects.push(("Hasted", hasted.turns));
+    }
+    if let Ok(slowed) = entry.get_component::<Slowed>() {
+        effects.push(("Slowed", slowed.turns));
+    }
+    if let Ok(poisoned) = entry.get_component::<Poisoned>() {
+        effects.push(("Poisoned", poisoned.turns));
+    }
+    if let Ok(confused) = entry.get_component::<Confused>() {
+        effects.push(("Confused", confused.turns));
+    }
+    if let Ok(asleep) = entry.get_component::<Asleep>() {
+        effects.push(("Asleep", asleep.turns));
+    }
+    if let Ok(afraid) = entry.get_component::<Afraid>() {
+        effects.push(("Afraid", afraid.turns));
+    }
+    effects
+}
@@ -0,0 +1,324 @@
+use super::faction::reaction;
+use crate::prelude::*;
+
+/// Where the aiming help is printed on the hud console, just above the message log
+const HELP_ROW: i32 = SCREEN_HEIGHT * 2 - 10;
+
+/// Items that have to be thrown or aimed send the player into targeting mode, anything
+/// else is used straight away. Systems calling this need read access to `Ranged`,
+/// `AreaOfEffect` and `Equippable`
+pub fn activate_or_aim(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    turn_state: &mut TurnState,
+    targeting: &mut Option<Targeting>,
+    user: Entity,
+    item: Entity,
+) {
+    // a bow is put on from the inventory, it is fired with its own key
+    let aimed = ecs.entry_ref(item).ok().and_then(|entry| {
+        if entry.get_component::<Equippable>().is_ok() {
+            return None;
+        }
+        let range = entry.get_component::<Ranged>().ok()?.range;
+        let radius = entry
+            .get_component::<AreaOfEffect>()
+            .map_or(0, |area| area.radius);
+        Some(Targeting::new(Aim::Item(item), range, radius))
+    });
+
+    match aimed {
+        Some(aimed) => {
+            *targeting = Some(aimed);
+            *turn_state = TurnState::Targeting;
+        }
+        None => {
+            commands.push((
+                (),
+                ActivateItem {
+                    used_by: user,
+                    item,
+                    target: None,
+                },
+            ));
+            *turn_state = TurnState::PlayerTurn;
+        }
+    }
+}
+
+/// The creature standing on a tile, if any, the player never aims at themselves
+fn creature_at(ecs: &SubWorld, index: &SpatialIndex, player: Entity, pt: Point) -> Option<Entity> {
+    index.entities_at(pt).iter().copied().find(|entity| {
+        *entity != player
+            && ecs
+                .entry_ref(*entity)
+                .is_ok_and(|entry| entry.get_component::<Health>().is_ok())
+    })
+}
+
+/// Whether what is being aimed moves the player to the target
+fn blinks(ecs: &SubWorld, aim: Aim) -> bool {
+    let source = match aim {
+        Aim::Weapon(entity) | Aim::Item(entity) | Aim::Ability(entity) => entity,
+    };
+    ecs.entry_ref(source)
+        .is_ok_and(|entry| entry.get_component::<ProvidesBlink>().is_ok())
+}
+
+/// Why the player can't fire at `cursor`, None when they can
+fn aim_problem(
+    ecs: &SubWorld,
+    map: &Map,
+    index: &SpatialIndex,
+    aim: &Targeting,
+    cursor: Point,
+) -> Option<&'static str> {
+    let (player, player_pos, player_fov) = <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+
+    if !player_fov.visible_tiles.contains(&cursor) {
+        Some("you can't see there")
+    } else if DistanceAlg::Pythagoras.distance2d(*player_pos, cursor) > aim.range as f32 {
+        Some("out of range")
+    } else if !has_line_of_fire(map, index, *player_pos, cursor) {
+        Some("no line of fire")
+    } else if matches!(aim.aim, Aim::Weapon(_))
+        && creature_at(ecs, index, *player, cursor).is_none()
+    {
+        // weapons need somebody to shoot at, thrown items can land on an empty tile
+        Some("nothing to shoot at")
+    } else if blinks(ecs, aim.aim) && (index.is_blocked(cursor) || !map.can_enter_tile(cursor)) {
+        Some("something is in the way")
+    } else if blinks(ecs, aim.aim) && !map.can_cross(cursor, None) {
+        // the player walks, and would only end up treading water
+        Some("the water is too deep")
+    } else {
+        None
+    }
+}
+
+/// Lets the player pick a tile in view and in range with the cursor keys, tab or the mouse.
+/// Firing ends the turn, escape gives up without spending one
+#[system]
+#[allow(clippy::too_many_arguments)]
+#[read_component(Player)]
+#[read_component(Point)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Faction)]
+#[read_component(Charmed)]
+#[read_component(Provoked)]
+#[read_component(ProvidesBlink)]
+pub fn targeting(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] mouse_pos: &Point,
+    #[resource] click: &MouseClick,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] targeting: &mut Option<Targeting>,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] index: &SpatialIndex,
+) {
+    let aim = match targeting.as_mut() {
+        Some(aim) => aim,
+        None => {
+            *turn_state = TurnState::AwaitingInput;
+            return;
+        }
+    };
+
+    let (player, player_pos, player_fov) = <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+
+    // everything in reach not on the player's side, nearest first
+    let mut candidates: Vec<Point> = player_fov
+        .visible_tiles
+        .iter()
+        .copied()
+        .filter(|pt| {
+            DistanceAlg::Pythagoras.distance2d(*player_pos, *pt) <= aim.range as f32
+                && has_line_of_fire(map, index, *player_pos, *pt)
+                && creature_at(ecs, index, *player, *pt)
+                    .is_some_and(|creature| reaction(ecs, *player, creature) != Reaction::Friendly)
+        })
+        .collect();
+    candidates.sort_by_key(|pt| {
+        let distance = DistanceAlg::Pythagoras.distance2d(*player_pos, *pt);
+        ((distance * 10.0) as i32, pt.y, pt.x)
+    });
+
+    let mut cursor = aim
+        .cursor
+        .unwrap_or_else(|| candidates.first().copied().unwrap_or(*player_pos));
+
+    // the mouse only takes over once it is moved, or clicked
+    let mouse = *mouse_pos + Point::new(camera.left_x, camera.top_y);
+    if (aim.mouse.is_some() && aim.mouse != Some(mouse)) || click.0 {
+        cursor = mouse;
+    }
+    aim.mouse = Some(mouse);
+
+    let mut fire = click.0;
+    if let Some(key) = key {
+        match key {
+            VirtualKeyCode::Up => cursor.y -= 1,
+            VirtualKeyCode::Down => cursor.y += 1,
+            VirtualKeyCode::Left => cursor.x -= 1,
+            VirtualKeyCode::Right => cursor.x += 1,
+            VirtualKeyCode::Tab if !candidates.is_empty() => {
+                let next = candidates
+                    .iter()
+                    .position(|pt| *pt == cursor)
+                    .map_or(0, |current| (current + 1) % candidates.len());
+                cursor = candidates[next];
+            }
+            VirtualKeyCode::Return | VirtualKeyCode::Space | VirtualKeyCode::F => fire = true,
+            VirtualKeyCode::Escape => {
+                *targeting = None;
+                *turn_state = TurnState::AwaitingInput;
+                return;
+            }
+            _ => {}
+        }
+    }
+    if !map.in_bounds(cursor) {
+        cursor = aim.cursor.unwrap_or(*player_pos);
+    }
+    aim.cursor = Some(cursor);
+
+    if !fire || aim_problem(ecs, map, index, aim, cursor).is_some() {
+        return;
+    }
+
+    match aim.aim {
+        Aim::Weapon(_) => {
+            if let Some(victim) = creature_at(ecs, index, *player, cursor) {
+                commands.push((
+                    (),
+                    WantsToAttack {
+                        attacker: *player,
+                        victim,
+                    },
+                ));
+            }
+        }
+        Aim::Item(item) => {
+            commands.push((
+                (),
+                ActivateItem {
+                    used_by: *player,
+                    item,
+                    target: Some(cursor),
+                },
+            ));
+        }
+        Aim::Ability(ability) => {
+            commands.push((
+                (),
+                WantsToCast {
+                    caster: *player,
+                    ability,
+                    target: Some(cursor),
+                },
+            ));
+        }
+    }
+
+    *targeting = None;
+    *turn_state = TurnState::PlayerTurn;
+}
+
+/// Highlights the flight path to the cursor, the tiles a blast would catch and whether
+/// the shot can be taken, with a reminder of the keys
+#[system]
+#[allow(clippy::borrowed_box)]
+#[read_component(Player)]
+#[read_component(Point)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Name)]
+#[read_component(ProvidesBlink)]
+pub fn targeting_render(
+    ecs: &SubWorld,
+    #[resource] targeting: &Option<Targeting>,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] theme: &Box<dyn MapTheme>,
+    #[resource] index: &SpatialIndex,
+) {
+    let (aim, cursor) = match targeting {
+        Some(aim) => match aim.cursor {
+            Some(cursor) => (aim, cursor),
+            None => return,
+        },
+        None => return,
+    };
+
+    let (player_pos, player_fov) = <(&Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    let problem = aim_problem(ecs, map, index, aim, cursor);
+
+    // map tiles are drawn again on top, tinted like the map render system tints them
+    let offset = Point::new(camera.left_x, camera.top_y);
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(0);
+    let mut tint = |pt: Point, color: (u8, u8, u8)| {
+        if map.in_bounds(pt) && player_fov.visible_tiles.contains(&pt) {
+            let glyph = theme.tile_to_render(map.tiles[map_idx(pt.x, pt.y)]);
+            draw_batch.set(pt - offset, ColorPair::new(color, BLACK), glyph);
+        }
+    };
+    flight_path(map, index, *player_pos, cursor)
+        .into_iter()
+        .for_each(|pt| tint(pt, YELLOW));
+    if aim.radius > 0 && problem.is_none() {
+        blast_area(map, cursor, aim.radius)
+            .into_iter()
+            .for_each(|pt| tint(pt, ORANGE));
+    }
+    tint(cursor, if problem.is_none() { CYAN } else { RED });
+    draw_batch.submit(1000).expect("Error rendering targeting");
+
+    let source = match aim.aim {
+        Aim::Weapon(entity) | Aim::Item(entity) | Aim::Ability(entity) => entity,
+    };
+    let name = ecs
+        .entry_ref(source)
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get_component::<Name>()
+                .ok()
+                .map(|name| name.0.clone())
+        })
+        .unwrap_or_default();
+
+    let mut help = DrawBatch::new();
+    help.target(3);
+    help.print_color_centered(
+        HELP_ROW,
+        match problem {
+            Some(problem) => format!("Aiming the {}: {}", name, problem),
+            None if aim.radius > 0 => format!("Aiming the {}, blast radius {}", name, aim.radius),
+            None => format!("Aiming the {}", name),
+        },
+        ColorPair::new(if problem.is_none() { CYAN } else { RED }, BLACK),
+    );
+    help.print_color_centered(
+        HELP_ROW + 1,
+        "Cursor keys or mouse to aim, TAB next target, ENTER or click to fire, ESC to cancel",
+        ColorPair::new(GRAY, BLACK),
+    );
+    help.submit(11000).expect("Error rendering targeting help");
+}
@@ -1,3 +1,6 @@
+use super::equipment::{equipment_summary, equipped_in};
+use super::faction::reaction;
+use super::status_effects::active_effects;
 use crate::prelude::*;
 
 #[system]
@@ -6,44 +9,129 @@ use crate::prelude::*;
 #[read_component(Health)]
 #[read_component(Player)]
 #[read_component(FieldOfView)]
-pub fn tooltips(ecs: &SubWorld, #[resource] camera: &Camera, #[resource] mouse_pos: &Point) {
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(AttackBonus)]
+#[read_component(DefenseBonus)]
+#[read_component(AiState)]
+#[read_component(Faction)]
+#[read_component(Charmed)]
+#[read_component(Provoked)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+#[read_component(Poisoned)]
+#[read_component(Confused)]
+#[read_component(Asleep)]
+#[read_component(Afraid)]
+pub fn tooltips(
+    ecs: &SubWorld,
+    #[resource] camera: &Camera,
+    #[resource] mouse_pos: &Point,
+    #[resource] index: &SpatialIndex,
+    #[resource] fields: &FlowFields,
+    #[resource] options: &GameOptions,
+) {
     // get player field of view
-    let player_fov = <&FieldOfView>::query()
+    let (player, player_fov) = <(Entity, &FieldOfView)>::query()
         .filter(component::<Player>())
         .iter(ecs)
-        .nth(0)
+        .next()
         .unwrap();
 
-    // including the entity here, includes the entity that owns the components
-    let mut positions = <(Entity, &Point, &Name)>::query();
-
     let offset = Point::new(camera.left_x, camera.top_y);
     let map_pos = *mouse_pos + offset;
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(3);
 
-    positions
-        .iter(ecs)
-        // filter for the mouse hovering exactly above the monster
-        .filter(|(_, pos, _)| **pos == map_pos
-            && player_fov.visible_tiles.contains(&pos))
-        .for_each(|(ent, _, name)| {
+    // what the monsters make of the hovered tile, steps to the player, away from them and to an item
+    if options.debug
+        && map_pos.x >= 0
+        && map_pos.x < SCREEN_WIDTH
+        && map_pos.y >= 0
+        && map_pos.y < SCREEN_HEIGHT
+    {
+        let idx = map_idx(map_pos.x, map_pos.y);
+        let value = |field: Option<&DijkstraMap>| match field.map(|field| field.map[idx]) {
+            Some(value) if value < f32::MAX => format!("{:.1}", value),
+            _ => "-".to_string(),
+        };
+        draw_batch.print_color(
+            *mouse_pos * 4 + Point::new(0, 1),
+            format!(
+                "chase {} flee {} item {}",
+                value(fields.chase()),
+                value(fields.flee()),
+                value(fields.items())
+            ),
+            ColorPair::new(MAGENTA, BLACK),
+        );
+    }
+
+    // only what the player can see gets a tooltip
+    if !player_fov.visible_tiles.contains(&map_pos) {
+        draw_batch.submit(110000).expect("Error rendering tooltips");
+        return;
+    }
+
+    index
+        .entities_at(map_pos)
+        .iter()
+        .filter_map(|ent| {
+            let name = ecs
+                .entry_ref(*ent)
+                .ok()?
+                .get_component::<Name>()
+                .ok()?
+                .clone();
+            Some((ent, name))
+        })
+        .for_each(|(ent, name)| {
             let mut screen_pos = *mouse_pos * 4;
-            screen_pos.y = screen_pos.y - 1;
+            screen_pos.y -= 1;
 
-            let display = if let Ok(_) = ecs
-                .entry_ref(*ent).unwrap().get_component::<Player>()
+            let display = if ecs
+                .entry_ref(*ent).unwrap().get_component::<Player>().is_ok()
             {
                 // show x & y of map of where player is for debugging
                 format!("x: {}, y: {}", &map_pos.x, &map_pos.y)
             } else {
                 // we don't query for the health component in positions,
                 // since we might support other tooltips also
-                if let Ok(health) = ecs
-                    .entry_ref(*ent).unwrap().get_component::<Health>()
-                {
-                    format!("{} : {} hp", &name.0, health.current)
+                let entry = ecs.entry_ref(*ent).unwrap();
+                if let Ok(health) = entry.get_component::<Health>() {
+                    // only creatures that are not out for the player's blood are pointed out
+                    let attitude = match reaction(ecs, *ent, *player) {
+                        Reaction::Friendly => " (ally)",
+                        Reaction::Neutral => " (neutral)",
+                        Reaction::Hostile => "",
+                    };
+                    let mut display = format!("{} : {} hp{}", &name.0, health.current, attitude);
+                    let effects: Vec<String> = active_effects(&entry)
+                        .iter()
+                        .map(|(effect, _)| effect.to_lowercase())
+                        .collect();
+                    if !effects.is_empty() {
+                        display = format!("{} [{}]", display, effects.join(", "));
+                    }
+                    if let Ok(state) = entry.get_component::<AiState>() {
+                        if options.debug {
+                            display = format!("{} {:?}", display, state);
+                        }
+                    }
+                    display
+                } else if let Some(summary) = equipment_summary(&entry) {
+                    // mention what is worn in the same slot to compare against
+                    let slot = entry.get_component::<Equippable>().unwrap().slot;
+                    let worn = equipped_in(ecs, *player, slot)
+                        .and_then(|item| ecs.entry_ref(item).ok())
+                        .and_then(|worn| {
+                            let name = worn.get_component::<Name>().ok()?.0.clone();
+                            Some(format!("{} ({})", name, equipment_summary(&worn)?))
+                        })
+                        .unwrap_or_else(|| "nothing".to_string());
+
+                    format!("{} ({}), wearing {}", &name.0, summary, worn)
                 } else {
                     name.0.clone()
                 }
@@ -0,0 +1,349 @@
+use super::combat::apply_damage;
+use super::equipment::equipped_in;
+use super::status_effects::inflict;
+use crate::prelude::*;
+
+/// Applies the effects of activated items, items stay entities throughout so
+/// effects are simply components attached to the item
+#[system]
+#[read_component(ActivateItem)]
+#[read_component(Consumable)]
+#[read_component(ProvidesHealing)]
+#[read_component(ProvidesDungeonMap)]
+#[read_component(ProvidesTeleport)]
+#[read_component(ProvidesBlink)]
+#[read_component(ProvidesVision)]
+#[read_component(VisionBoost)]
+#[read_component(ProvidesHaste)]
+#[read_component(ProvidesSlow)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+#[read_component(Poisoned)]
+#[read_component(Confused)]
+#[read_component(Asleep)]
+#[read_component(Afraid)]
+#[read_component(InflictsStatus)]
+#[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(Point)]
+#[read_component(ProvidesCharm)]
+#[read_component(Faction)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(Name)]
+#[read_component(AreaOfEffect)]
+#[read_component(InflictsDamage)]
+#[read_component(BlocksTile)]
+#[read_component(ExperienceValue)]
+#[read_component(Locomotion)]
+#[write_component(Health)]
+#[write_component(FieldOfView)]
+#[write_component(Experience)]
+pub fn use_items(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &mut Map,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] game_log: &mut GameLog,
+    #[resource] index: &mut SpatialIndex,
+) {
+    let mut healing_to_apply = Vec::<(Entity, i32)>::new();
+    let mut vision_to_apply = Vec::<(Entity, ProvidesVision)>::new();
+    let mut damage_to_apply = Vec::<(Entity, Entity, String, i32)>::new();
+
+    <(Entity, &ActivateItem)>::query()
+        .iter(ecs)
+        .for_each(|(message, activate)| {
+            if let Ok(item) = ecs.entry_ref(activate.item) {
+                let name = item
+                    .get_component::<Name>()
+                    .map(|name| name.0.clone())
+                    .unwrap_or_default();
+                // monsters casting abilities go through here as well
+                let by_player = ecs
+                    .entry_ref(activate.used_by)
+                    .is_ok_and(|user| user.get_component::<Player>().is_ok());
+
+                // thrown and aimed items catch whoever stands on the target, or in the blast
+                let caught: Option<Vec<Entity>> = activate.target.map(|target| {
+                    let area = match item.get_component::<AreaOfEffect>() {
+                        Ok(aoe) => blast_area(map, target, aoe.radius),
+                        Err(_) => vec![target],
+                    };
+                    <(Entity, &Point)>::query()
+                        .filter(component::<Health>())
+                        .iter(ecs)
+                        .filter(|(_, pos)| area.contains(pos))
+                        .map(|(entity, _)| *entity)
+                        .collect()
+                });
+
+                if let Ok(healing) = item.get_component::<ProvidesHealing>() {
+                    healing_to_apply.push((activate.used_by, healing.amount));
+                }
+
+                if by_player && item.get_component::<ProvidesDungeonMap>().is_ok() {
+                    map.revealed_tiles.iter_mut().for_each(|t| *t = true);
+                    game_log.push(format!("The {} reveals the level", name), CYAN);
+                }
+
+                if item.get_component::<ProvidesTeleport>().is_ok() {
+                    let locomotion = ecs
+                        .entry_ref(activate.used_by)
+                        .ok()
+                        .and_then(|user| user.get_component::<Locomotion>().ok().copied());
+                    let destinations: Vec<Point> = (0..map.tiles.len())
+                        .map(|idx| map.index_to_point2d(idx))
+                        .filter(|pt| map.is_safe(*pt, locomotion) && !index.is_blocked(*pt))
+                        .collect();
+
+                    // the movement system takes care of the camera and field of view
+                    if let Some(destination) = rng.random_slice_entry(&destinations) {
+                        commands.push((
+                            (),
+                            WantsToMove {
+                                entity: activate.used_by,
+                                destination: *destination,
+                            },
+                        ));
+                        if by_player {
+                            game_log.push(format!("The {} whisks you away", name), CYAN);
+                        }
+                    }
+                }
+
+                // the movement system makes sure nobody got to the tile first
+                if let (Ok(_), Some(destination)) =
+                    (item.get_component::<ProvidesBlink>(), activate.target)
+                {
+                    commands.push((
+                        (),
+                        WantsToMove {
+                            entity: activate.used_by,
+                            destination,
+                        },
+                    ));
+                }
+
+                if let Ok(vision) = item.get_component::<ProvidesVision>() {
+                    vision_to_apply.push((activate.used_by, *vision));
+                }
+
+                // haste from another potion adds up, it is only counted down as the user acts
+                if let Ok(haste) = item.get_component::<ProvidesHaste>() {
+                    let remaining = ecs
+                        .entry_ref(activate.used_by)
+                        .ok()
+                        .and_then(|user| user.get_component::<Hasted>().ok().map(|h| h.turns))
+                        .unwrap_or(0);
+
+                    commands.add_component(
+                        activate.used_by,
+                        Hasted {
+                            turns: remaining + haste.turns,
+                        },
+                    );
+                    if by_player {
+                        game_log.push("You feel yourself speed up", CYAN);
+                    }
+                }
+
+                if let Ok(charm) = item.get_component::<ProvidesCharm>() {
+                    let user = ecs.entry_ref(activate.used_by).ok();
+                    let user_pos = user
+                        .as_ref()
+                        .and_then(|user| user.get_component::<Point>().ok().copied());
+                    let seen = user
+                        .and_then(|user| user.get_component::<FieldOfView>().ok().cloned())
+                        .map(|fov| fov.visible_tiles)
+                        .unwrap_or_default();
+
+                    // the closest creature in view that belongs to a faction
+                    let closest = user_pos.and_then(|user_pos| {
+                        <(Entity, &Point, &Name)>::query()
+                            .filter(component::<Faction>())
+                            .iter(ecs)
+                            .filter(|(entity, pos, _)| {
+                                **entity != activate.used_by && seen.contains(pos)
+                            })
+                            .min_by_key(|(_, pos, _)| {
+                                let distance = DistanceAlg::Manhattan.distance2d(user_pos, **pos);
+                                (distance as i32, pos.y, pos.x)
+                            })
+                    });
+
+                    match closest {
+                        Some((monster, _, monster_name)) => {
+                            commands.remove_component::<Provoked>(*monster);
+                            commands.add_component(
+                                *monster,
+                                Charmed {
+                                    by: activate.used_by,
+                                    turns: charm.turns,
+                                },
+                            );
+                            game_log.push(
+                                format!(
+                                    "The {} is charmed and fights at your side",
+                                    monster_name.0
+                                ),
+                                PINK,
+                            );
+                        }
+                        None => game_log.push(format!("The {} finds nobody to charm", name), GRAY),
+                    }
+                }
+
+                if let Ok(slow) = item.get_component::<ProvidesSlow>() {
+                    let seen = ecs
+                        .entry_ref(activate.used_by)
+                        .ok()
+                        .and_then(|user| user.get_component::<FieldOfView>().ok().cloned())
+                        .map(|fov| fov.visible_tiles)
+                        .unwrap_or_default();
+
+                    let mut slowed = 0;
+                    <(Entity, &Point)>::query()
+                        .filter(component::<Enemy>())
+                        .iter(ecs)
+                        .filter(|(_, pos)| seen.contains(pos))
+                        .for_each(|(enemy, _)| {
+                            commands.add_component(*enemy, Slowed { turns: slow.turns });
+                            slowed += 1;
+                        });
+                    game_log.push(format!("The {} slows {} monsters down", name, slowed), CYAN);
+                }
+
+                if let Ok(inflicts) = item.get_component::<InflictsDamage>() {
+                    let caught = caught.clone().unwrap_or_default();
+                    if caught.is_empty() {
+                        game_log.push(format!("The {} hits nothing", name), GRAY);
+                    }
+                    caught.into_iter().for_each(|victim| {
+                        damage_to_apply.push((
+                            activate.used_by,
+                            victim,
+                            name.clone(),
+                            inflicts.damage,
+                        ));
+                    });
+                }
+
+                // sleep, confusion and the like befall whoever was caught, or every enemy in
+                // view when the item is not aimed
+                if let Ok(inflicts) = item.get_component::<InflictsStatus>() {
+                    let victims: Vec<Entity> = match caught {
+                        Some(caught) => caught,
+                        None => {
+                            let seen = ecs
+                                .entry_ref(activate.used_by)
+                                .ok()
+                                .and_then(|user| user.get_component::<FieldOfView>().ok().cloned())
+                                .map(|fov| fov.visible_tiles)
+                                .unwrap_or_default();
+
+                            <(Entity, &Point)>::query()
+                                .filter(component::<Enemy>())
+                                .iter(ecs)
+                                .filter(|(_, pos)| seen.contains(pos))
+                                .map(|(enemy, _)| *enemy)
+                                .collect()
+                        }
+                    };
+                    if victims.is_empty() {
+                        game_log.push(format!("The {} has nobody to affect", name), GRAY);
+                    }
+                    victims.into_iter().for_each(|victim| {
+                        inflict(ecs, commands, game_log, victim, inflicts.effect, true);
+                    });
+                }
+
+                // equipment is put on or taken off, replacing whatever was in its slot
+                if let Ok(equippable) = item.get_component::<Equippable>() {
+                    if item.get_component::<Equipped>().is_ok() {
+                        commands.remove_component::<Equipped>(activate.item);
+                        game_log.push(format!("You take off the {}", name), CYAN);
+                    } else {
+                        if let Some(current) = equipped_in(ecs, activate.used_by, equippable.slot) {
+                            commands.remove_component::<Equipped>(current);
+                        }
+
+                        commands.add_component(
+                            activate.item,
+                            Equipped {
+                                owner: activate.used_by,
+                                slot: equippable.slot,
+                            },
+                        );
+                        game_log.push(format!("You equip the {}", name), CYAN);
+                    }
+                }
+
+                if item.get_component::<Consumable>().is_ok() {
+                    commands.remove(activate.item);
+                }
+            }
+
+            commands.remove(*message);
+        });
+
+    healing_to_apply.iter().for_each(|(target, amount)| {
+        let mut entry = ecs.entry_mut(*target).unwrap();
+        let is_player = entry.get_component::<Player>().is_ok();
+        if let Ok(health) = entry.get_component_mut::<Health>() {
+            health.current = i32::min(health.max, health.current + amount);
+            if is_player {
+                game_log.push(format!("You are healed for {}", amount), GREEN);
+            }
+        }
+    });
+
+    damage_to_apply
+        .into_iter()
+        .for_each(|(user, victim, item_name, damage)| {
+            let victim_name = match ecs.entry_ref(victim) {
+                // already brought down by something else this turn
+                Ok(entry) if entry.get_component::<Health>().is_ok_and(|h| h.current < 1) => return,
+                Ok(entry) => entry
+                    .get_component::<Name>()
+                    .map(|name| name.0.clone())
+                    .unwrap_or_else(|_| "Something".to_string()),
+                Err(_) => return,
+            };
+            let killed = apply_damage(ecs, commands, index, Some(user), victim, damage);
+            game_log.push_combat(&CombatEvent {
+                attacker: item_name,
+                victim: victim_name,
+                outcome: CombatOutcome::Hit,
+                damage,
+                killed,
+            });
+        });
+
+    vision_to_apply.iter().for_each(|(target, vision)| {
+        let mut entry = ecs.entry_mut(*target).unwrap();
+
+        // drinking another one only extends the duration, the bonus does not stack
+        if let Ok(boost) = entry.get_component::<VisionBoost>() {
+            commands.add_component(
+                *target,
+                VisionBoost {
+                    turns: boost.turns + vision.turns,
+                    ..*boost
+                },
+            );
+        } else if let Ok(fov) = entry.get_component_mut::<FieldOfView>() {
+            fov.radius += vision.bonus;
+            fov.is_dirty = true;
+            game_log.push("Your sight reaches further", CYAN);
+
+            commands.add_component(
+                *target,
+                VisionBoost {
+                    bonus: vision.bonus,
+                    turns: vision.turns,
+                },
+            );
+        }
+    });
+}
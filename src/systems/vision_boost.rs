@@ -0,0 +1,19 @@
+use crate::prelude::*;
+
+/// Counts down temporary vision boosts, restoring the normal radius once they run out
+#[system]
+#[write_component(VisionBoost)]
+#[write_component(FieldOfView)]
+pub fn vision_boost(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+    <(Entity, &mut VisionBoost, &mut FieldOfView)>::query()
+        .iter_mut(ecs)
+        .for_each(|(entity, boost, fov)| {
+            boost.turns -= 1;
+
+            if boost.turns < 1 {
+                fov.radius -= boost.bonus;
+                fov.is_dirty = true;
+                commands.remove_component::<VisionBoost>(*entity);
+            }
+        });
+}
@@ -0,0 +1,76 @@
+use crate::prelude::*;
+
+/// What the player is taking aim with
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Aim {
+    /// the equipped ranged weapon, it attacks whatever stands on the target
+    Weapon(Entity),
+    /// a carried item, it is used on the target once picked
+    Item(Entity),
+    /// one of the player's abilities, it is cast at the target once picked
+    Ability(Entity),
+}
+
+/// A resource describing the shot being lined up while the game is in `TurnState::Targeting`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Targeting {
+    pub aim: Aim,
+    pub range: i32,
+    /// tiles around the target that are hit as well, 0 for a single tile
+    pub radius: i32,
+    /// the tile aimed at, picked by the targeting system when it is still empty
+    pub cursor: Option<Point>,
+    /// where the mouse was last seen, so keyboard aiming is not undone by a resting mouse
+    pub mouse: Option<Point>,
+}
+
+impl Targeting {
+    pub fn new(aim: Aim, range: i32, radius: i32) -> Self {
+        Self {
+            aim,
+            range,
+            radius,
+            cursor: None,
+            mouse: None,
+        }
+    }
+}
+
+/// A resource that is true on the frame the left mouse button was clicked
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MouseClick(pub bool);
+
+/// The tiles something thrown or shot from `from` towards `to` passes, it stops at the
+/// first wall or blocking creature on the way
+pub fn flight_path(map: &Map, index: &SpatialIndex, from: Point, to: Point) -> Vec<Point> {
+    let mut path = Vec::new();
+
+    for pt in line2d(LineAlg::Bresenham, from, to) {
+        if pt == from {
+            continue;
+        }
+        path.push(pt);
+        if pt == to || !map.can_enter_tile(pt) || index.is_blocked(pt) {
+            break;
+        }
+    }
+
+    path
+}
+
+/// Whether a shot from `from` makes it all the way to `to`
+pub fn has_line_of_fire(map: &Map, index: &SpatialIndex, from: Point, to: Point) -> bool {
+    from != to && flight_path(map, index, from, to).last() == Some(&to)
+}
+
+/// The tiles caught in a blast of `radius` around `target`, walls shelter what is behind them
+pub fn blast_area(map: &Map, target: Point, radius: i32) -> Vec<Point> {
+    let mut area: Vec<Point> = field_of_view_set(target, radius, map)
+        .into_iter()
+        .filter(|pt| map.can_enter_tile(*pt))
+        .collect();
+
+    // the same order every time, so effects play out the same for every seed
+    area.sort_by_key(|pt| (pt.y, pt.x));
+    area
+}
@@ -1,8 +1,16 @@
-#[derive(Copy, Clone, Debug, PartialEq)]
+use crate::prelude::*;
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TurnState {
     AwaitingInput,
     PlayerTurn,
+    ShowingInventory,
+    DroppingItem,
+    ShowingHistory,
+    /// the player picks a tile for a ranged weapon or item, see `Targeting`
+    Targeting,
     MonsterTurn,
+    NextLevel,
     GameOver,
     Victory,
 }